@@ -0,0 +1,173 @@
+use std::io::{BufRead, Write};
+
+use clap::Args;
+use farcoin::{
+    ExtendedPrivateKey, Keystore, Mnemonic, Multisig, PrivateKey, PublicKey, Scheme, Signer,
+};
+
+#[derive(Debug, Args)]
+pub struct KeyArgs {
+    /// Private key in hex
+    #[arg(long, env = "FARCOIN_KEY", hide_env_values = true)]
+    key: Option<String>,
+    /// Encrypted keystore file holding the private key
+    #[arg(long, env = "FARCOIN_KEYSTORE")]
+    keystore: Option<String>,
+    /// Keystore password (prompted for when not given)
+    #[arg(long, env = "FARCOIN_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+    #[command(flatten)]
+    mnemonic: MnemonicArgs,
+    /// Unix socket of a signing daemon (`serve-signer`) to sign with instead of a local key
+    #[cfg(unix)]
+    #[arg(long, env = "FARCOIN_SIGNER")]
+    signer: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct MnemonicArgs {
+    /// Mnemonic seed phrase to derive the private key from
+    #[arg(long, env = "FARCOIN_MNEMONIC", hide_env_values = true)]
+    mnemonic: Option<String>,
+    /// Optional passphrase protecting the mnemonic seed phrase
+    #[arg(
+        long,
+        env = "FARCOIN_PASSPHRASE",
+        hide_env_values = true,
+        default_value = ""
+    )]
+    passphrase: String,
+    /// Index of the wallet key derived from the mnemonic seed phrase
+    #[arg(long, default_value_t = 0)]
+    index: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct MultisigArgs {
+    /// Number of owners that must sign
+    #[arg(long)]
+    threshold: u32,
+    /// Public key of an owner (repeat for each owner, always in the same order)
+    #[arg(long = "owner", required = true)]
+    owners: Vec<String>,
+}
+
+impl KeyArgs {
+    pub fn signer(&self, scheme: Scheme) -> Result<Box<dyn Signer>, String> {
+        #[cfg(unix)]
+        if let Some(path) = &self.signer {
+            if self.key.is_some() || self.keystore.is_some() || self.mnemonic.mnemonic.is_some() {
+                return Err("--signer cannot be combined with a local key!".into());
+            }
+
+            let signer = farcoin::RemoteSigner::connect(path)
+                .map_err(|error| format!("Failed to connect to signer: {}", error))?;
+
+            return Ok(Box::new(signer));
+        }
+
+        Ok(Box::new(self.load(scheme)?))
+    }
+
+    // Keystores record the scheme of their key, so the scheme only applies to the other sources.
+    pub fn load(&self, scheme: Scheme) -> Result<PrivateKey, String> {
+        match (&self.key, &self.keystore, &self.mnemonic.mnemonic) {
+            (Some(key), None, None) => Ok(parse_private_key(key)?.with_scheme(scheme)),
+            (None, None, Some(_)) => self.mnemonic.derive(scheme),
+            (None, Some(path), None) => {
+                let keystore = Keystore::load(path).map_err(|error| error.to_string())?;
+
+                let password = match &self.password {
+                    Some(password) => password.clone(),
+                    None => read_password("Password: ")?,
+                };
+
+                keystore
+                    .decrypt(&password)
+                    .map_err(|error| error.to_string())
+            }
+            _ => Err("Exactly one of --key, --keystore or --mnemonic is required!".into()),
+        }
+    }
+}
+
+impl MnemonicArgs {
+    pub fn derive(&self, scheme: Scheme) -> Result<PrivateKey, String> {
+        let Some(phrase) = &self.mnemonic else {
+            return Err("A mnemonic seed phrase is required!".into());
+        };
+
+        let Some(mnemonic) = Mnemonic::parse(phrase) else {
+            return Err("Invalid mnemonic seed phrase!".into());
+        };
+
+        let Some(key) = ExtendedPrivateKey::from_mnemonic(&mnemonic, &self.passphrase)
+            .and_then(|master| master.derive_wallet(self.index))
+        else {
+            return Err("Failed to derive key!".into());
+        };
+
+        Ok(key.private_key().clone().with_scheme(scheme))
+    }
+}
+
+impl MultisigArgs {
+    pub fn load(&self) -> Result<Multisig, String> {
+        let keys = self
+            .owners
+            .iter()
+            .map(|owner| {
+                PublicKey::try_from(owner.trim())
+                    .map_err(|error| format!("Invalid owner key {}: {}!", owner, error))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Multisig::new(self.threshold, keys).ok_or_else(|| {
+            format!(
+                "Multisig wallets need between 1 and {} distinct owners and a threshold no larger than the number of owners!",
+                Multisig::MAX_KEYS
+            )
+        })
+    }
+}
+
+pub fn parse_private_key(key: &str) -> Result<PrivateKey, String> {
+    PrivateKey::try_from(key.trim()).map_err(|error| format!("Invalid private key: {}!", error))
+}
+
+pub fn read_password(prompt: &str) -> Result<String, String> {
+    eprint!("{}", prompt);
+
+    std::io::stderr()
+        .flush()
+        .map_err(|error| error.to_string())?;
+
+    let mut password = String::new();
+
+    std::io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .map_err(|error| error.to_string())?;
+
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+pub fn save_keystore(key: &PrivateKey, path: &str, password: Option<String>) -> Result<(), String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            let password = read_password("New password: ")?;
+
+            if read_password("Repeat password: ")? != password {
+                return Err("Passwords do not match!".into());
+            }
+
+            password
+        }
+    };
+
+    Keystore::encrypt(rand::thread_rng(), key, &password)
+        .map_err(|error| error.to_string())?
+        .save(path)
+        .map_err(|error| error.to_string())
+}
@@ -0,0 +1,1000 @@
+mod keys;
+mod node;
+
+use std::{fs, io::Read};
+
+use clap::{Args, Parser, Subcommand};
+use farcoin::{
+    Address, Amount, BlockData, ChainParams, Expiry, ExtendedPrivateKey, Hash, Lock, Memo,
+    Mnemonic, Network, Output, PartialTransaction, Preimage, PrivateKey, PublicKey, Scheme,
+    Signature, Signer, UtcDateTime,
+};
+use keys::{KeyArgs, MnemonicArgs, MultisigArgs};
+use node::Node;
+
+#[derive(Debug, Parser)]
+#[command(name = "farcoin-cli", about = "Manage FarCoin keys and wallets")]
+struct Cli {
+    /// Node to submit data to and query
+    #[arg(
+        long,
+        global = true,
+        env = "FARCOIN_NODE",
+        default_value = "http://127.0.0.1:3000"
+    )]
+    node: String,
+    /// Network to build addresses for (mainnet, testnet or regtest)
+    #[arg(long, global = true, env = "FARCOIN_NETWORK", default_value = "mainnet", value_parser = parse_network)]
+    network: Network,
+    /// Signature scheme of the private key (ecdsa or schnorr)
+    #[arg(long, global = true, env = "FARCOIN_SCHEME", default_value = "ecdsa", value_parser = parse_scheme)]
+    scheme: Scheme,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Generate a new private key
+    GenerateKey {
+        /// Store the key in an encrypted keystore file instead of printing it
+        #[arg(long)]
+        keystore: Option<String>,
+        /// Keystore password (prompted for when not given)
+        #[arg(long, env = "FARCOIN_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// Generate a new mnemonic seed phrase
+    GenerateMnemonic {
+        /// Number of words in the phrase (12, 15, 18, 21 or 24)
+        #[arg(long, default_value_t = 12)]
+        words: usize,
+        /// Optional passphrase protecting the mnemonic seed phrase
+        #[arg(
+            long,
+            env = "FARCOIN_PASSPHRASE",
+            hide_env_values = true,
+            default_value = ""
+        )]
+        passphrase: String,
+    },
+    /// Derive a wallet key from a mnemonic seed phrase
+    DeriveKey {
+        #[command(flatten)]
+        mnemonic: MnemonicArgs,
+        /// Store the key in an encrypted keystore file instead of printing it
+        #[arg(long)]
+        keystore: Option<String>,
+        /// Keystore password (prompted for when not given)
+        #[arg(long, env = "FARCOIN_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// Store an existing private key in an encrypted keystore file
+    ImportKey {
+        #[arg(long, env = "FARCOIN_KEY", hide_env_values = true)]
+        key: String,
+        #[arg(long)]
+        keystore: String,
+        /// Keystore password (prompted for when not given)
+        #[arg(long, env = "FARCOIN_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// Show the public key of a private key
+    PublicKey {
+        #[command(flatten)]
+        key: KeyArgs,
+    },
+    /// Show the address of a private or public key
+    Address {
+        /// Public key to show the address of instead of a private key
+        #[arg(long)]
+        public_key: Option<String>,
+        #[command(flatten)]
+        key: KeyArgs,
+    },
+    /// Build and sign a wallet creation request
+    CreateWallet {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Student ID of the new wallet
+        #[arg(long)]
+        id: u64,
+    },
+    /// Build and sign a transaction
+    Transaction {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Build an unsigned transaction from this public key to be signed offline with `sign`
+        #[arg(long, conflicts_with_all = ["key", "keystore", "mnemonic"])]
+        from: Option<String>,
+        /// Address or public key of the receiving wallet
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: Amount,
+        #[arg(long)]
+        fee: Amount,
+        /// Short description shown in the wallet histories, such as an invoice ID
+        #[arg(long)]
+        memo: Option<String>,
+        #[command(flatten)]
+        expiry: ExpiryArgs,
+        /// Encrypt the memo so that only the receiver can read it (needs the receiver's public
+        /// key as --to)
+        #[arg(long, requires = "memo")]
+        encrypt_memo: bool,
+        /// Keep the transaction out of blocks below this height
+        #[arg(long, conflicts_with = "lock_time")]
+        lock_height: Option<u64>,
+        /// Keep the transaction out of blocks made before this time (e.g.
+        /// "2024-05-17T08:00:00 UTC")
+        #[arg(long)]
+        lock_time: Option<UtcDateTime>,
+    },
+    /// Build and sign a transaction paying several wallets at once
+    BatchTransaction {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Build an unsigned transaction from this public key to be signed offline with `sign`
+        #[arg(long, conflicts_with_all = ["key", "keystore", "mnemonic"])]
+        from: Option<String>,
+        /// Address or public key of a receiving wallet and the amount it gets, as
+        /// `<address>=<amount>` (repeat for each receiver)
+        #[arg(long = "pay", required = true)]
+        outputs: Vec<String>,
+        /// Fee paid once for the whole transaction, on top of the amounts
+        #[arg(long)]
+        fee: Amount,
+        /// Short description shown in the wallet histories, such as an invoice ID
+        #[arg(long)]
+        memo: Option<String>,
+        #[command(flatten)]
+        expiry: ExpiryArgs,
+    },
+    /// Generate a secret preimage and the hash lock it unlocks
+    GeneratePreimage,
+    /// Build and sign a hash time-locked contract paying a wallet that reveals the preimage of
+    /// the hash lock, or refunding the sender after the timeout
+    CreateHtlc {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Address or public key of the wallet that can claim the contract
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: Amount,
+        #[arg(long)]
+        fee: Amount,
+        /// SHA-256 hash of the preimage that claims the contract
+        #[arg(long)]
+        hash_lock: Hash,
+        /// Block height from which the contract can only be refunded
+        #[arg(
+            long,
+            conflicts_with = "timeout_time",
+            required_unless_present = "timeout_time"
+        )]
+        timeout_height: Option<u64>,
+        /// Time from which the contract can only be refunded (e.g. "2024-05-17T08:00:00 UTC")
+        #[arg(long)]
+        timeout_time: Option<UtcDateTime>,
+    },
+    /// Build and sign a claim of a hash time-locked contract with its preimage
+    ClaimHtlc {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// ID of the contract, as printed by create-htlc
+        #[arg(long)]
+        htlc: Hash,
+        #[arg(long)]
+        preimage: Preimage,
+    },
+    /// Build and sign a refund of a hash time-locked contract after its timeout
+    RefundHtlc {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// ID of the contract, as printed by create-htlc
+        #[arg(long)]
+        htlc: Hash,
+    },
+    /// Show the address of a multisig wallet
+    MultisigAddress {
+        #[command(flatten)]
+        multisig: MultisigArgs,
+    },
+    /// Build a multisig wallet creation request as a partially signed transaction with your
+    /// signature
+    CreateMultisigWallet {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        multisig: MultisigArgs,
+        /// ID of the new wallet
+        #[arg(long)]
+        id: u64,
+    },
+    /// Build a transaction from a multisig wallet as a partially signed transaction with your
+    /// signature
+    MultisigTransaction {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        multisig: MultisigArgs,
+        /// Address or public key of the receiving wallet
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: Amount,
+        #[arg(long)]
+        fee: Amount,
+        /// Short description shown in the wallet histories, such as an invoice ID
+        #[arg(long)]
+        memo: Option<String>,
+        #[command(flatten)]
+        expiry: ExpiryArgs,
+        /// Encrypt the memo so that only the receiver can read it (needs the receiver's public
+        /// key as --to)
+        #[arg(long, requires = "memo")]
+        encrypt_memo: bool,
+    },
+    /// Add your signature to a partially signed transaction (reads standard input when no file
+    /// is given)
+    Sign {
+        #[command(flatten)]
+        key: KeyArgs,
+        file: Option<String>,
+    },
+    /// Merge the signatures of several copies of a partially signed transaction
+    Combine {
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Show the data and signers of a partially signed transaction (reads standard input when
+    /// no file is given)
+    Inspect { file: Option<String> },
+    /// Keep a private key in this process and sign for other processes through a Unix socket
+    #[cfg(unix)]
+    ServeSigner {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Path of the Unix socket to listen on
+        #[arg(long)]
+        socket: String,
+    },
+    /// Submit signed data or a complete partially signed transaction to the node (reads standard
+    /// input when no file is given)
+    Submit { file: Option<String> },
+    /// Query the balance of a wallet by public key or student ID
+    Balance { wallet: String },
+    /// Query the transaction history of a wallet by public key or student ID
+    History {
+        wallet: String,
+        /// Decrypt the memos encrypted to the wallet with its private key
+        #[arg(long)]
+        decrypt: bool,
+        #[command(flatten)]
+        key: KeyArgs,
+    },
+    /// Query a hash time-locked contract, including the preimage once it is claimed
+    Htlc { htlc: Hash },
+    /// Query the number of coins in circulation
+    Supply {
+        /// Block height to query instead of the tip of the chain
+        #[arg(long)]
+        height: Option<u64>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct ExpiryArgs {
+    /// Reject the transaction in blocks above this height
+    #[arg(long, conflicts_with = "expiry_time")]
+    expiry_height: Option<u64>,
+    /// Reject the transaction in blocks made after this time (e.g. "2024-05-17T08:00:00 UTC")
+    #[arg(long)]
+    expiry_time: Option<UtcDateTime>,
+}
+
+impl ExpiryArgs {
+    fn expiry(self) -> Option<Expiry> {
+        self.expiry_height
+            .map(Expiry::Height)
+            .or(self.expiry_time.map(Expiry::Time))
+    }
+}
+
+fn parse_network(network: &str) -> Result<Network, String> {
+    Network::try_from(network).map_err(|error| error.to_string())
+}
+
+fn parse_scheme(scheme: &str) -> Result<Scheme, String> {
+    Scheme::try_from(scheme).map_err(|error| error.to_string())
+}
+
+fn print_public_key(network: Network, key: &PublicKey) {
+    println!("Public key: {}", String::from(key));
+    println!("Address: {}", String::from(&Address::new(network, key)));
+}
+
+fn parse_receiver(network: Network, to: &str) -> Result<Address, String> {
+    let receiver = if let Ok(receiver) = Address::try_from(to.trim()) {
+        receiver
+    } else if let Ok(receiver_key) = PublicKey::try_from(to.trim()) {
+        Address::new(network, &receiver_key)
+    } else {
+        return Err("Invalid receiver address or key!".into());
+    };
+
+    if receiver.network() != network {
+        return Err(format!("Receiver is not a {} address!", network.name()));
+    }
+
+    Ok(receiver)
+}
+
+fn parse_output(network: Network, output: &str) -> Result<Output, String> {
+    let Some((to, amount)) = output.split_once('=') else {
+        return Err(format!("Expected <address>=<amount>, got {}!", output));
+    };
+
+    let amount = amount
+        .trim()
+        .parse::<Amount>()
+        .map_err(|error| format!("Invalid amount: {}!", error))?;
+
+    if amount.is_zero() {
+        return Err("Amount must be greater than zero!".into());
+    }
+
+    Ok(Output {
+        receiver: parse_receiver(network, to)?,
+        amount,
+    })
+}
+
+fn check_amount(network: Network, amount: Amount, fee: Amount) -> Result<(), String> {
+    if amount.is_zero() {
+        return Err("Amount must be greater than zero!".into());
+    }
+
+    let params = ChainParams::for_network(network);
+
+    if !params.check_fee(fee, amount) {
+        return Err(format!(
+            "Fee must be at least {} and less than the amount!",
+            params.min_fee
+        ));
+    }
+
+    Ok(())
+}
+
+// Encrypts the memo when given the receiver's public key.
+fn parse_memo(
+    network: Network,
+    memo: Option<String>,
+    encrypt_to: Option<&str>,
+) -> Result<Option<Memo>, String> {
+    let Some(memo) = memo.map(Memo::Text) else {
+        return Ok(None);
+    };
+
+    let params = ChainParams::for_network(network);
+
+    if !params.check_memo(Some(&memo)) {
+        return Err(format!(
+            "Memo must be at most {} bytes on a single line!",
+            params.max_memo_len
+        ));
+    }
+
+    let (Memo::Text(text), Some(to)) = (&memo, encrypt_to) else {
+        return Ok(Some(memo));
+    };
+
+    let Ok(receiver_key) = PublicKey::try_from(to.trim()) else {
+        return Err("Encrypted memos need the receiver's public key as --to!".into());
+    };
+
+    Ok(Some(Memo::encrypt(rand::thread_rng(), &receiver_key, text)))
+}
+
+// History entries keep their fields under the name of their kind, as in `{"Receive": {..}}`.
+// Memos encrypted to other keys are left as they are.
+fn decrypt_memos(transaction: &mut serde_json::Value, key: &PrivateKey) {
+    let entries = transaction
+        .as_object_mut()
+        .into_iter()
+        .flat_map(|kinds| kinds.values_mut());
+
+    for entry in entries {
+        let Some(memo) = entry.get_mut("memo") else {
+            continue;
+        };
+
+        let Ok(Memo::Encrypted(encrypted)) = serde_json::from_value(memo.clone()) else {
+            continue;
+        };
+
+        if let Ok(text) = encrypted.decrypt(key) {
+            *memo = text.into();
+        }
+    }
+}
+
+// The socket is bound in a new directory only we can enter and moved into place once it is private,
+// so that nobody can connect to it in between, whatever the umask.
+#[cfg(unix)]
+fn bind_socket(socket: &str) -> std::io::Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::UnixListener,
+    };
+
+    let staging = format!("{}.{}.tmp", socket, std::process::id());
+    let staged = format!("{}/socket", staging);
+
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+
+    let listener = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&staged, socket)?;
+
+        Ok(listener)
+    });
+
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&staging);
+
+    listener
+}
+
+fn read_input(file: Option<String>) -> Result<String, String> {
+    match file {
+        Some(file) => fs::read_to_string(file).map_err(|error| error.to_string()),
+        None => {
+            let mut input = String::new();
+
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|error| error.to_string())?;
+
+            Ok(input)
+        }
+    }
+}
+
+fn parse_psbt(input: &str) -> Result<PartialTransaction, String> {
+    PartialTransaction::try_from(input.trim())
+        .map_err(|error| format!("Invalid partially signed transaction: {}!", error))
+}
+
+fn print_psbt(psbt: &PartialTransaction) {
+    eprintln!(
+        "{} signatures collected, {} required",
+        psbt.signed(),
+        psbt.threshold()
+    );
+
+    println!("{}", String::from(psbt));
+}
+
+fn sign_psbt(mut psbt: PartialTransaction, signer: &dyn Signer) -> Result<(), String> {
+    if !psbt.sign(signer) {
+        return Err("Key is not one of the signers!".into());
+    }
+
+    print_psbt(&psbt);
+
+    Ok(())
+}
+
+fn sign(mut data: BlockData, signer: &dyn Signer) -> Result<String, String> {
+    if !data.sign(signer) {
+        return Err("Failed to sign data!".into());
+    }
+
+    serde_json::to_string_pretty(&data).map_err(|error| error.to_string())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let mut rng = rand::thread_rng();
+
+    match cli.command {
+        Command::GenerateKey { keystore, password } => {
+            let key = PrivateKey::random(&mut rng).with_scheme(cli.scheme);
+
+            match keystore {
+                Some(path) => keys::save_keystore(&key, &path, password)?,
+                None => println!("Private key: {}", String::from(&key)),
+            }
+
+            print_public_key(cli.network, &PublicKey::from(&key));
+        }
+        Command::GenerateMnemonic { words, passphrase } => {
+            let Some(mnemonic) = Mnemonic::generate(&mut rng, words) else {
+                return Err("Invalid number of words!".into());
+            };
+
+            let Some(key) = ExtendedPrivateKey::from_mnemonic(&mnemonic, &passphrase)
+                .and_then(|master| master.derive_wallet(0))
+            else {
+                return Err("Failed to derive key!".into());
+            };
+
+            println!("Mnemonic: {}", mnemonic.phrase().as_str());
+            let key = key.private_key().clone().with_scheme(cli.scheme);
+
+            print_public_key(cli.network, &PublicKey::from(&key));
+        }
+        Command::DeriveKey {
+            mnemonic,
+            keystore,
+            password,
+        } => {
+            let key = mnemonic.derive(cli.scheme)?;
+
+            match keystore {
+                Some(path) => keys::save_keystore(&key, &path, password)?,
+                None => println!("Private key: {}", String::from(&key)),
+            }
+
+            print_public_key(cli.network, &PublicKey::from(&key));
+        }
+        Command::ImportKey {
+            key,
+            keystore,
+            password,
+        } => {
+            let key = keys::parse_private_key(&key)?.with_scheme(cli.scheme);
+
+            keys::save_keystore(&key, &keystore, password)?;
+
+            print_public_key(cli.network, &PublicKey::from(&key));
+        }
+        Command::Address { public_key, key } => {
+            let public_key = match public_key {
+                Some(public_key) => PublicKey::try_from(public_key.trim())
+                    .map_err(|error| format!("Invalid public key: {}!", error))?,
+                None => key.signer(cli.scheme)?.public_key(),
+            };
+
+            println!("{}", String::from(&Address::new(cli.network, &public_key)));
+        }
+        Command::PublicKey { key } => {
+            let key = key.signer(cli.scheme)?;
+
+            println!("{}", String::from(&key.public_key()));
+        }
+        Command::CreateWallet { key, id } => {
+            let key = key.signer(cli.scheme)?;
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            let wallet = BlockData::CreateWallet {
+                id,
+                key: key.public_key(),
+                time,
+                signature: Signature::placeholder(),
+            };
+
+            println!("{}", sign(wallet, key.as_ref())?);
+        }
+        Command::Transaction {
+            key,
+            from,
+            to,
+            amount,
+            fee,
+            memo,
+            expiry,
+            encrypt_memo,
+            lock_height,
+            lock_time,
+        } => {
+            let receiver = parse_receiver(cli.network, &to)?;
+
+            check_amount(cli.network, amount, fee)?;
+
+            let memo = parse_memo(cli.network, memo, encrypt_memo.then_some(to.as_str()))?;
+            let lock = lock_height.map(Lock::Height).or(lock_time.map(Lock::Time));
+            let expiry = expiry.expiry();
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            match from {
+                Some(from) => {
+                    let sender = PublicKey::try_from(from.trim())
+                        .map_err(|error| format!("Invalid sender public key: {}!", error))?;
+
+                    let transaction = BlockData::Transaction {
+                        fee,
+                        amount,
+                        time,
+                        sender,
+                        receiver,
+                        memo,
+                        lock,
+                        expiry,
+                        signature: Signature::placeholder(),
+                    };
+
+                    print_psbt(&PartialTransaction::new(transaction));
+                }
+                None => {
+                    let key = key.signer(cli.scheme)?;
+
+                    let transaction = BlockData::Transaction {
+                        fee,
+                        amount,
+                        time,
+                        sender: key.public_key(),
+                        receiver,
+                        memo,
+                        lock,
+                        expiry,
+                        signature: Signature::placeholder(),
+                    };
+
+                    println!("{}", sign(transaction, key.as_ref())?);
+                }
+            }
+        }
+        Command::BatchTransaction {
+            key,
+            from,
+            outputs,
+            fee,
+            memo,
+            expiry,
+        } => {
+            let outputs = outputs
+                .iter()
+                .map(|output| parse_output(cli.network, output))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if Amount::checked_sum(outputs.iter().map(|output| output.amount)).is_none() {
+                return Err("Total amount is too large!".into());
+            }
+
+            let params = ChainParams::for_network(cli.network);
+
+            if !params.check_batch_fee(fee) {
+                return Err(format!("Fee must be at least {}!", params.min_fee));
+            }
+
+            let memo = parse_memo(cli.network, memo, None)?;
+            let expiry = expiry.expiry();
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            match from {
+                Some(from) => {
+                    let sender = PublicKey::try_from(from.trim())
+                        .map_err(|error| format!("Invalid sender public key: {}!", error))?;
+
+                    let transaction = BlockData::BatchTransaction {
+                        fee,
+                        time,
+                        sender,
+                        outputs,
+                        memo,
+                        expiry,
+                        signature: Signature::placeholder(),
+                    };
+
+                    print_psbt(&PartialTransaction::new(transaction));
+                }
+                None => {
+                    let key = key.signer(cli.scheme)?;
+
+                    let transaction = BlockData::BatchTransaction {
+                        fee,
+                        time,
+                        sender: key.public_key(),
+                        outputs,
+                        memo,
+                        expiry,
+                        signature: Signature::placeholder(),
+                    };
+
+                    println!("{}", sign(transaction, key.as_ref())?);
+                }
+            }
+        }
+        Command::GeneratePreimage => {
+            let preimage = Preimage::random(&mut rng);
+
+            println!("Preimage: {}", preimage);
+            println!("Hash lock: {}", preimage.hash_lock());
+        }
+        Command::CreateHtlc {
+            key,
+            to,
+            amount,
+            fee,
+            hash_lock,
+            timeout_height,
+            timeout_time,
+        } => {
+            let key = key.signer(cli.scheme)?;
+            let receiver = parse_receiver(cli.network, &to)?;
+
+            check_amount(cli.network, amount, fee)?;
+
+            let Some(timeout) = timeout_height
+                .map(Lock::Height)
+                .or(timeout_time.map(Lock::Time))
+            else {
+                return Err("Missing timeout!".into());
+            };
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            let htlc = BlockData::CreateHtlc {
+                fee,
+                amount,
+                time,
+                sender: key.public_key(),
+                receiver,
+                hash_lock,
+                timeout,
+                signature: Signature::placeholder(),
+            };
+
+            // The contract is known by the hash of the data creating it.
+            eprintln!("HTLC: {}", htlc.hash());
+
+            println!("{}", sign(htlc, key.as_ref())?);
+        }
+        Command::ClaimHtlc {
+            key,
+            htlc,
+            preimage,
+        } => {
+            let key = key.signer(cli.scheme)?;
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            let claim = BlockData::ClaimHtlc {
+                htlc,
+                preimage,
+                time,
+                receiver: key.public_key(),
+                signature: Signature::placeholder(),
+            };
+
+            println!("{}", sign(claim, key.as_ref())?);
+        }
+        Command::RefundHtlc { key, htlc } => {
+            let key = key.signer(cli.scheme)?;
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            let refund = BlockData::RefundHtlc {
+                htlc,
+                time,
+                sender: key.public_key(),
+                signature: Signature::placeholder(),
+            };
+
+            println!("{}", sign(refund, key.as_ref())?);
+        }
+        Command::MultisigAddress { multisig } => {
+            let multisig = multisig.load()?;
+
+            println!(
+                "{}",
+                String::from(&Address::multisig(cli.network, &multisig))
+            );
+        }
+        Command::CreateMultisigWallet { key, multisig, id } => {
+            let key = key.signer(cli.scheme)?;
+            let owners = multisig.load()?;
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            let wallet = BlockData::CreateMultisigWallet {
+                id,
+                signatures: vec![None; owners.keys().len()],
+                owners,
+                time,
+            };
+
+            sign_psbt(PartialTransaction::new(wallet), key.as_ref())?;
+        }
+        Command::MultisigTransaction {
+            key,
+            multisig,
+            to,
+            amount,
+            fee,
+            memo,
+            expiry,
+            encrypt_memo,
+        } => {
+            let key = key.signer(cli.scheme)?;
+            let sender = multisig.load()?;
+            let receiver = parse_receiver(cli.network, &to)?;
+
+            check_amount(cli.network, amount, fee)?;
+
+            let memo = parse_memo(cli.network, memo, encrypt_memo.then_some(to.as_str()))?;
+
+            let Some(time) = UtcDateTime::now() else {
+                return Err("Failed to obtain time!".into());
+            };
+
+            let transaction = BlockData::MultisigTransaction {
+                fee,
+                amount,
+                time,
+                signatures: vec![None; sender.keys().len()],
+                sender,
+                receiver,
+                memo,
+                expiry: expiry.expiry(),
+            };
+
+            sign_psbt(PartialTransaction::new(transaction), key.as_ref())?;
+        }
+        Command::Sign { key, file } => {
+            let key = key.signer(cli.scheme)?;
+            let psbt = parse_psbt(&read_input(file)?)?;
+
+            sign_psbt(psbt, key.as_ref())?;
+        }
+        Command::Combine { files } => {
+            let mut psbts = files
+                .into_iter()
+                .map(|file| parse_psbt(&read_input(Some(file))?))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter();
+
+            let Some(mut combined) = psbts.next() else {
+                return Err("No partially signed transactions given!".into());
+            };
+
+            for psbt in psbts {
+                if !combined.combine(&psbt) {
+                    return Err("Partially signed transactions are for different data!".into());
+                }
+            }
+
+            print_psbt(&combined);
+        }
+        Command::Inspect { file } => {
+            let psbt = parse_psbt(&read_input(file)?)?;
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(psbt.data()).map_err(|error| error.to_string())?
+            );
+
+            for (signer, signature) in psbt.signers().iter().zip(psbt.signatures()) {
+                let status = if signature.is_some() {
+                    "signed"
+                } else {
+                    "missing"
+                };
+
+                println!("{} {}", String::from(*signer), status);
+            }
+
+            println!(
+                "{} signatures collected, {} required",
+                psbt.signed(),
+                psbt.threshold()
+            );
+        }
+        #[cfg(unix)]
+        Command::ServeSigner { key, socket } => {
+            let key = key.load(cli.scheme)?;
+
+            let listener = bind_socket(&socket).map_err(|error| error.to_string())?;
+
+            eprintln!(
+                "Signing with {} on {}",
+                String::from(&key.public_key()),
+                socket
+            );
+
+            farcoin::serve_signer(&listener, &key).map_err(|error| error.to_string())?;
+        }
+        Command::Submit { file } => {
+            let input = read_input(file)?;
+
+            let response = match PartialTransaction::try_from(input.trim()) {
+                Ok(psbt) => Node::new(&cli.node).submit_psbt(&psbt)?,
+                Err(_) => {
+                    let data: BlockData =
+                        serde_json::from_str(&input).map_err(|error| error.to_string())?;
+
+                    Node::new(&cli.node).submit(&data)?
+                }
+            };
+
+            if !response.valid {
+                return Err(response.message);
+            }
+
+            println!("{}", response.message);
+        }
+        Command::Balance { wallet } => {
+            let response = Node::new(&cli.node).balance(&wallet)?;
+
+            println!("{}", response.balance);
+        }
+        Command::History {
+            wallet,
+            decrypt,
+            key,
+        } => {
+            let key = match decrypt {
+                true => Some(key.load(cli.scheme)?),
+                false => None,
+            };
+
+            let response = Node::new(&cli.node).history(&wallet)?;
+
+            if !response.valid {
+                return Err(response.message);
+            }
+
+            for (time, mut transaction) in response.history {
+                if let Some(key) = &key {
+                    decrypt_memos(&mut transaction, key);
+                }
+
+                println!("{} {}", time, transaction);
+            }
+        }
+        Command::Htlc { htlc } => {
+            let response = Node::new(&cli.node).htlc(htlc)?;
+
+            let (true, Some(htlc)) = (response.valid, response.htlc) else {
+                return Err(response.message);
+            };
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&htlc).map_err(|error| error.to_string())?
+            );
+        }
+        Command::Supply { height } => {
+            let response = Node::new(&cli.node).supply(height)?;
+
+            if !response.valid {
+                return Err(response.message);
+            }
+
+            println!("Supply: {}", response.supply);
+            println!("Next block reward: {}", response.next_reward);
+            println!("Maximum reward supply: {}", response.max_reward_supply);
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(message) = run(Cli::parse()) {
+        eprintln!("Error: {}", message);
+
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,106 @@
+use farcoin::{Amount, BlockData, Hash, PartialTransaction};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize)]
+pub struct StatusResponse {
+    pub valid: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceResponse {
+    pub balance: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SupplyResponse {
+    pub valid: bool,
+    pub message: String,
+    pub supply: Amount,
+    pub next_reward: Amount,
+    pub max_reward_supply: Amount,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryResponse {
+    pub valid: bool,
+    pub message: String,
+    pub history: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HtlcResponse {
+    pub valid: bool,
+    pub message: String,
+    pub htlc: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct PsbtRequest {
+    psbt: String,
+}
+
+#[derive(Debug, Serialize)]
+struct WalletRequest<'a> {
+    public_key: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct SupplyRequest {
+    height: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct HtlcRequest {
+    htlc: Hash,
+}
+
+pub struct Node {
+    url: String,
+}
+
+impl Node {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T, String> {
+        ureq::post(&format!("{}{}", self.url, path))
+            .send_json(body)
+            .map_err(|error| error.to_string())?
+            .into_json()
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn submit(&self, data: &BlockData) -> Result<StatusResponse, String> {
+        self.post("/submit", data)
+    }
+
+    pub fn submit_psbt(&self, psbt: &PartialTransaction) -> Result<StatusResponse, String> {
+        self.post(
+            "/psbt/submit",
+            &PsbtRequest {
+                psbt: String::from(psbt),
+            },
+        )
+    }
+
+    pub fn balance(&self, wallet: &str) -> Result<BalanceResponse, String> {
+        self.post("/balance", &WalletRequest { public_key: wallet })
+    }
+
+    pub fn history(&self, wallet: &str) -> Result<HistoryResponse, String> {
+        self.post("/history", &WalletRequest { public_key: wallet })
+    }
+
+    pub fn supply(&self, height: Option<u64>) -> Result<SupplyResponse, String> {
+        self.post("/supply", &SupplyRequest { height })
+    }
+
+    pub fn htlc(&self, htlc: Hash) -> Result<HtlcResponse, String> {
+        self.post("/htlc", &HtlcRequest { htlc })
+    }
+}
@@ -3,7 +3,7 @@ use axum::{
     response::{Html, IntoResponse},
     Extension, Json,
 };
-use farcoin::PublicKey;
+use farcoin::{Address, PublicKey};
 use serde::{Deserialize, Serialize};
 
 use crate::WorldHandle;
@@ -19,6 +19,7 @@ pub struct Request {
 
 #[derive(Debug, Serialize)]
 struct Response {
+    address: String,
     balance: String,
 }
 
@@ -27,18 +28,21 @@ pub async fn post(
     Extension(world): Extension<WorldHandle>,
 ) -> impl IntoResponse {
     let Ok(world) = world.lock() else {
-        return (StatusCode::CREATED, Json(Response { balance: "Internal Server Error".into() }));
+        return (StatusCode::CREATED, Json(Response { address: String::new(), balance: "Internal Server Error".into() }));
     };
 
-    let public_key = if let Ok(public_key) = PublicKey::try_from(request.public_key.as_str()) {
-        public_key
+    let address = if let Ok(address) = Address::try_from(request.public_key.as_str()) {
+        address
+    } else if let Ok(public_key) = PublicKey::try_from(request.public_key.as_str()) {
+        world.address(&public_key)
     } else if let Ok(student_id) = request.public_key.parse::<u64>() {
-        if let Some(public_key) = world.wallet_ids.get(&student_id) {
-            public_key.clone()
+        if let Some(address) = world.wallet_ids.get(&student_id) {
+            *address
         } else {
             return (
                 StatusCode::CREATED,
                 Json(Response {
+                    address: String::new(),
                     balance: "Student not found!".into(),
                 }),
             );
@@ -47,18 +51,20 @@ pub async fn post(
         return (
             StatusCode::CREATED,
             Json(Response {
-                balance: "Invalid public key!".into(),
+                address: String::new(),
+                balance: "Invalid address or public key!".into(),
             }),
         );
     };
 
-    let Some(wallet) = world.wallets.get(&public_key) else {
-        return (StatusCode::CREATED, Json(Response { balance: "Wallet not found!".into() }));
+    let Some(wallet) = world.wallets.get(&address) else {
+        return (StatusCode::CREATED, Json(Response { address: String::new(), balance: "Wallet not found!".into() }));
     };
 
     (
         StatusCode::CREATED,
         Json(Response {
+            address: String::from(&address),
             balance: format!("{}", wallet.balance),
         }),
     )
@@ -0,0 +1,75 @@
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use farcoin::{Address, PublicKey, UtcDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{world::Transaction, WorldHandle};
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    public_key: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    valid: bool,
+    message: String,
+    history: Vec<(UtcDateTime, Transaction)>,
+}
+
+pub async fn post(
+    Json(request): Json<Request>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(world) = world.lock() else {
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into(), history: vec![] }));
+    };
+
+    let address = if let Ok(address) = Address::try_from(request.public_key.as_str()) {
+        address
+    } else if let Ok(public_key) = PublicKey::try_from(request.public_key.as_str()) {
+        world.address(&public_key)
+    } else if let Ok(student_id) = request.public_key.parse::<u64>() {
+        if let Some(address) = world.wallet_ids.get(&student_id) {
+            *address
+        } else {
+            return (
+                StatusCode::CREATED,
+                Json(Response {
+                    valid: false,
+                    message: "Student not found!".into(),
+                    history: vec![],
+                }),
+            );
+        }
+    } else {
+        return (
+            StatusCode::CREATED,
+            Json(Response {
+                valid: false,
+                message: "Invalid address or public key!".into(),
+                history: vec![],
+            }),
+        );
+    };
+
+    let Some(wallet) = world.wallets.get(&address) else {
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Wallet not found!".into(), history: vec![] }));
+    };
+
+    let mut history: Vec<_> = wallet
+        .transaction_history
+        .iter()
+        .map(|(time, transaction)| (time.clone(), transaction.clone()))
+        .collect();
+
+    history.sort_by(|a, b| a.0.cmp(&b.0));
+
+    (
+        StatusCode::CREATED,
+        Json(Response {
+            valid: true,
+            message: format!("{} transactions", history.len()),
+            history,
+        }),
+    )
+}
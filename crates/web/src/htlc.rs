@@ -0,0 +1,43 @@
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use farcoin::Hash;
+use serde::{Deserialize, Serialize};
+
+use crate::{world::Htlc, WorldHandle};
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    htlc: Hash,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    valid: bool,
+    message: String,
+    htlc: Option<Htlc>,
+}
+
+pub async fn post(
+    Json(request): Json<Request>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(world) = world.lock() else {
+        return (StatusCode::CREATED, Json(Response { message: "Internal Server Error".into(), ..Default::default() }));
+    };
+
+    let Some(htlc) = world.htlcs.get(&request.htlc) else {
+        return (StatusCode::CREATED, Json(Response { message: "Contract not found!".into(), ..Default::default() }));
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(Response {
+            valid: true,
+            message: format!(
+                "{} coins locked to {}",
+                htlc.amount,
+                String::from(&htlc.receiver)
+            ),
+            htlc: Some(htlc.clone()),
+        }),
+    )
+}
@@ -1,5 +1,13 @@
 mod balance;
+mod history;
+mod htlc;
 mod index;
+mod psbt;
+mod submit;
+mod supply;
+mod sync;
+#[cfg(test)]
+mod test_util;
 mod transaction;
 mod validate;
 mod wallet;
@@ -8,7 +16,6 @@ mod world;
 use std::{
     net::SocketAddr,
     sync::{Arc, Mutex},
-    time::Duration,
 };
 
 use axum::{
@@ -16,84 +23,226 @@ use axum::{
     Extension, Router,
 };
 use axum_extra::routing::SpaRouter;
-use farcoin::{Block, BlockChain, BlockData, Hash, PrivateKey, PublicKey, UtcDateTime};
+use clap::Parser;
+use farcoin::{
+    Block, BlockChain, BlockData, ChainParams, Hash, Network, PrivateKey, PublicKey, Signature,
+    Signer, UtcDateTime,
+};
 use tokio::time::sleep;
 use world::WorldState;
 
 pub type WorldHandle = Arc<Mutex<WorldState>>;
 
-async fn mine_worker(handle: WorldHandle, genesis_keys: (PublicKey, PrivateKey)) {
-    let mut rng = rand::thread_rng();
+#[derive(Debug, Parser)]
+struct Options {
+    /// Port to serve the web interface and node API on
+    #[arg(long, default_value_t = 3000)]
+    port: u16,
+    /// Peer node to synchronize the chain with (e.g. http://127.0.0.1:3000), may be repeated
+    #[arg(long)]
+    peer: Vec<String>,
+    /// Create a new genesis block even when peers are given
+    #[arg(long)]
+    genesis: bool,
+    /// Network whose addresses the node accepts (mainnet, testnet or regtest)
+    #[arg(long, default_value = "mainnet", value_parser = parse_network)]
+    network: Network,
+    /// Unix socket of a signing daemon (`farcoin-cli serve-signer`) holding the miner key,
+    /// instead of generating a new key
+    #[cfg(unix)]
+    #[arg(long)]
+    signer: Option<std::path::PathBuf>,
+}
+
+fn parse_network(network: &str) -> Result<Network, String> {
+    Network::try_from(network).map_err(|error| error.to_string())
+}
+
+fn create_signer(options: &Options) -> Box<dyn Signer> {
+    #[cfg(unix)]
+    if let Some(path) = &options.signer {
+        println!("Connecting to signer...");
+
+        let signer = farcoin::RemoteSigner::connect(path).expect("connected to signer");
+
+        return Box::new(signer);
+    }
+
+    println!("Generating genesis keys...");
+
+    Box::new(PrivateKey::random(rand::thread_rng()))
+}
 
-    let empty_signature = genesis_keys
-        .1
-        .sign_with_rng(&mut rng, &Hash::empty())
-        .expect("signed empty hash");
+async fn mine_worker(handle: WorldHandle, signer: Box<dyn Signer>) {
+    let miner = signer.public_key();
+
+    let Ok(params) = handle.lock().map(|world| world.params().clone()) else {
+        println!("Failed to lock world!");
+        return;
+    };
 
     loop {
-        sleep(Duration::from_millis(5000)).await;
+        sleep(params.block_interval).await;
+
+        let block = {
+            let Ok(mut world) = handle.lock() else {
+                println!("Failed to lock world!");
+                continue;
+            };
+
+            next_block(&mut world, &miner)
+        };
+
+        let Some(mut block) = block else {
+            continue;
+        };
+
+        // The world stays unlocked while signing, which a remote signer may take a while for.
+        let mined = block.mine(&params, signer.as_ref());
 
         let Ok(mut world) = handle.lock() else {
             println!("Failed to lock world!");
             continue;
         };
 
-        if world.waiting.len() > 0 {
-            println!("Processing {} events...", world.waiting.len());
+        if !mined {
+            println!("Failed to mine block!");
+            world.requeue(block.data);
+            continue;
+        }
 
-            let (id, previous_hash) = if let Some(last) = world.chain.blocks().last() {
-                (last.id + 1, last.hash.clone())
-            } else {
-                (0, Hash::empty())
-            };
+        // Another block may have been added while the world was unlocked.
+        if world.chain.tip().map(|tip| tip.hash) != Some(block.previous_hash) {
+            println!("Chain moved on while mining!");
+            world.requeue(block.data);
+            continue;
+        }
 
-            let Some(time) = UtcDateTime::now() else {
-                println!("Failed to obtain time!");
-                continue;
-            };
+        let data = block.data.clone();
 
-            let mut block = Block {
-                id,
-                nonce: 0,
-                miner: genesis_keys.0.clone(),
-                time,
-                data: world.waiting.clone(),
-                previous_hash,
-                signature: empty_signature.clone(),
-                hash: Hash::empty(),
-            };
+        if !world.add_block(block) {
+            println!("Failed to validate new block!");
+            world.requeue(data);
+            continue;
+        }
 
-            world.waiting.clear();
+        println!("Complete!");
+    }
+}
 
-            if !BlockChain::mine_block_with_rng(&mut rng, &mut block, &genesis_keys.1) {
-                println!("Failed to mine block!");
-                continue;
-            }
+// Takes the events for the next block out of the queue, leaving the block to be mined.
+fn next_block(world: &mut WorldState, miner: &PublicKey) -> Option<Block> {
+    if world.waiting.is_empty() {
+        return None;
+    }
 
-            if !world.add_block(block) {
-                println!("Failed to validate new block!");
-                continue;
-            }
+    // A miner without a wallet could not be paid, so the node leaves its events to the peers it
+    // relays them to until the wallet exists.
+    let address = world.address(miner);
+
+    if !world.wallets.contains_key(&address) {
+        return None;
+    }
+
+    let last = world.chain.tip()?;
+
+    let (id, previous_hash) = (last.id + 1, last.hash);
+
+    let Some(time) = UtcDateTime::now() else {
+        println!("Failed to obtain time!");
+        return None;
+    };
+
+    let (expired, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut world.waiting)
+        .into_iter()
+        .partition(|data| world.is_expired(data, id, &time));
+
+    world.waiting = waiting;
+
+    if !expired.is_empty() {
+        println!("Dropped {} expired events.", expired.len());
+    }
+
+    // Time-locked events wait in the queue until a block can include them.
+    if !world
+        .waiting
+        .iter()
+        .any(|data| world.is_unlocked(data, id, &time))
+    {
+        return None;
+    }
+
+    println!("Processing {} events...", world.waiting.len());
+
+    let mut block = Block {
+        id,
+        nonce: 0,
+        miner: miner.clone(),
+        time,
+        data: vec![],
+        previous_hash,
+        signature: Signature::placeholder(),
+        hash: Hash::zero(),
+    };
+
+    // Each event is applied to the world as it is picked and checked against what the events
+    // before it leave, so two spends of the same coins never end up in one block.
+    let mut pending = vec![];
 
-            println!("Complete!");
+    let reward = BlockData::Reward {
+        amount: world.params().reward(id),
+        receiver: address,
+    };
+
+    if let Some(undo) = world.apply_pending(&reward, &block) {
+        pending.push(undo);
+        block.data.push(reward);
+    }
+
+    let rewards = block.data.len();
+
+    // Whatever does not fit or is still locked waits for a later block.
+    let max_block_data = world.params().max_block_data;
+
+    for waiting in std::mem::take(&mut world.waiting) {
+        if block.data.len() >= max_block_data || !world.is_unlocked(&waiting, id, &block.time) {
+            world.waiting.push(waiting);
+            continue;
+        }
+
+        if !waiting.verify() || world.is_applied(&waiting) {
+            println!("Dropping an event that can never be valid!");
+            continue;
+        }
+
+        // Events short of coins or wallets that may still arrive, for example because an event
+        // picked before them spent the coins, wait for a later block.
+        match world.apply_pending(&waiting, &block) {
+            Some(undo) => {
+                pending.push(undo);
+                block.data.push(waiting);
+            }
+            None => world.waiting.push(waiting),
         }
     }
-}
 
-#[tokio::main]
-async fn main() {
-    let mut rng = rand::thread_rng();
+    world.revert_pending(pending);
 
-    println!("Generating genesis keys...");
+    if block.data.len() == rewards {
+        return None;
+    }
 
-    let genesis_key = PrivateKey::random(&mut rng);
-    let public_key = PublicKey::from(&genesis_key);
+    Some(block)
+}
 
-    let empty_signature = genesis_key.sign(&Hash::empty()).unwrap();
+fn create_chain(signer: &dyn Signer, params: ChainParams) -> BlockChain {
+    let public_key = signer.public_key();
+
+    let empty_signature = Signature::placeholder();
 
     println!("Creating block chain...");
 
-    let mut chain = BlockChain::new();
+    let mut chain = BlockChain::new(params);
 
     let mut genesis_block = Block {
         id: 0,
@@ -101,14 +250,14 @@ async fn main() {
         miner: public_key.clone(),
         time: UtcDateTime::now().unwrap(),
         data: vec![],
-        previous_hash: Hash::empty(),
+        previous_hash: Hash::zero(),
         signature: empty_signature.clone(),
-        hash: Hash::empty(),
+        hash: Hash::zero(),
     };
 
     println!("Mining genesis block...");
 
-    if !BlockChain::mine_block_with_rng(&mut rng, &mut genesis_block, &genesis_key) {
+    if !chain.mine_block(&mut genesis_block, signer) {
         panic!("Failed to mine genesis block!");
     }
 
@@ -127,9 +276,7 @@ async fn main() {
         panic!("what");
     };
 
-    *signature = genesis_key
-        .sign_with_rng(&mut rng, &test_wallet_hash)
-        .unwrap();
+    *signature = signer.sign(&test_wallet_hash).unwrap();
 
     let mut test_block = Block {
         id: 1,
@@ -137,21 +284,44 @@ async fn main() {
         miner: public_key.clone(),
         time: UtcDateTime::now().unwrap(),
         data: vec![test_wallet],
-        previous_hash: chain.blocks().last().unwrap().hash.clone(),
+        previous_hash: chain.blocks().last().unwrap().hash,
         signature: empty_signature.clone(),
-        hash: Hash::empty(),
+        hash: Hash::zero(),
     };
 
-    if !BlockChain::mine_block_with_rng(&mut rng, &mut test_block, &genesis_key) {
+    if !chain.mine_block(&mut test_block, signer) {
         panic!("Failed to mine test block!");
     }
 
     chain.add_block(test_block);
 
+    chain
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::parse();
+
+    let signer = create_signer(&options);
+
+    let params = ChainParams::for_network(options.network);
+
+    let chain = if !options.peer.is_empty() && !options.genesis {
+        println!("Synchronizing block chain from peer...");
+
+        BlockChain::new(params)
+    } else {
+        create_chain(signer.as_ref(), params)
+    };
+
     let world_state = WorldHandle::new(Mutex::new(
         WorldState::new(chain).expect("valid world state"),
     ));
 
+    for peer in &options.peer {
+        tokio::spawn(sync::sync_worker(world_state.clone(), peer.clone()));
+    }
+
     println!("Server started!");
 
     tokio::join!(
@@ -162,20 +332,234 @@ async fn main() {
                 .route("/transaction", post(transaction::post))
                 .route("/balance", get(balance::get))
                 .route("/balance", post(balance::post))
+                .route("/history", post(history::post))
+                .route("/supply", post(supply::post))
+                .route("/htlc", post(htlc::post))
                 .route("/wallet", get(wallet::get))
                 .route("/wallet", post(wallet::post))
                 .route("/validate", get(validate::get))
                 .route("/validate", post(validate::post))
+                .route("/submit", post(submit::post))
+                .route("/psbt/combine", post(psbt::combine))
+                .route("/psbt/submit", post(psbt::submit))
+                .route("/sync/headers", post(sync::headers))
+                .route("/sync/blocks", post(sync::blocks))
                 .layer(Extension(world_state.clone()))
                 .merge(SpaRouter::new("/assets", "frontend/assets").index_file("error.html"));
 
-            let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
+            let addr = SocketAddr::from(([127, 0, 0, 1], options.port));
 
             axum::Server::bind(&addr)
                 .serve(app.into_make_service())
                 .await
                 .unwrap();
         },
-        mine_worker(world_state.clone(), (public_key, genesis_key))
+        mine_worker(world_state.clone(), signer)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use farcoin::{Amount, Expiry, Lock};
+
+    use super::*;
+    use crate::test_util::{
+        address, block, coins, create_wallet, key, mine, now, paid_transaction, signed,
+        transaction, world,
+    };
+
+    fn conditional_transaction(
+        sender: &PrivateKey,
+        receiver: &PrivateKey,
+        lock: Option<Lock>,
+        expiry: Option<Expiry>,
+    ) -> BlockData {
+        let transaction = BlockData::Transaction {
+            fee: Amount::ZERO,
+            amount: coins(10),
+            time: now(),
+            sender: PublicKey::from(sender),
+            receiver: address(receiver),
+            memo: None,
+            lock,
+            expiry,
+            signature: Signature::placeholder(),
+        };
+
+        signed(transaction, sender)
+    }
+
+    #[test]
+    fn blocks_never_spend_the_same_coins_twice() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![create_wallet(&student, 1)]));
+
+        let balance = world.wallets[&address(&student)].balance;
+        let first = transaction(&student, &miner, coins(60));
+        let second = transaction(&student, &miner, coins(60));
+
+        world.waiting = vec![first.clone(), second.clone()];
+
+        let mut block = next_block(&mut world, &PublicKey::from(&miner)).unwrap();
+
+        let hashes: Vec<_> = block.data.iter().map(BlockData::hash).collect();
+
+        assert_eq!(hashes.len(), 2);
+        assert!(matches!(block.data[0], BlockData::Reward { .. }));
+        assert_eq!(hashes[1], first.hash());
+        assert_eq!(world.wallets[&address(&student)].balance, balance);
+
+        // The second spend could still go through if the student is paid more coins.
+        assert_eq!(world.waiting.len(), 1);
+        assert_eq!(world.waiting[0].hash(), second.hash());
+
+        assert!(world.chain.mine_block(&mut block, &miner));
+        assert!(world.add_block(block));
+    }
+
+    #[test]
+    fn events_that_can_never_be_valid_are_dropped() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![create_wallet(&student, 1)]));
+
+        let payment = transaction(&student, &miner, coins(10));
+
+        assert!(mine(&mut world, &miner, vec![payment.clone()]));
+
+        let mut forged = transaction(&student, &miner, coins(10));
+
+        if let BlockData::Transaction { signature, .. } = &mut forged {
+            *signature = Signature::placeholder();
+        }
+
+        world.waiting = vec![payment, forged];
+
+        assert!(next_block(&mut world, &PublicKey::from(&miner)).is_none());
+        assert!(world.waiting.is_empty());
+    }
+
+    #[test]
+    fn locked_events_wait_until_they_unlock() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![create_wallet(&student, 1)]));
+
+        let height = world.chain.tip().unwrap().id;
+        let lock = Some(Lock::Height(height + 2));
+        let locked = conditional_transaction(&student, &miner, lock, None);
+
+        assert!(submit::queue(&mut world, locked.clone()).valid);
+        assert!(next_block(&mut world, &PublicKey::from(&miner)).is_none());
+        assert_eq!(world.waiting.len(), 1);
+
+        // Other nodes do not take it early either.
+        assert!(!world.add_block(block(&world, &miner, vec![locked.clone()])));
+
+        assert!(mine(&mut world, &miner, vec![]));
+
+        let mut block = next_block(&mut world, &PublicKey::from(&miner)).unwrap();
+
+        assert_eq!(block.data.len(), 2);
+        assert_eq!(block.data[1].hash(), locked.hash());
+        assert!(world.chain.mine_block(&mut block, &miner));
+        assert!(world.add_block(block));
+        assert!(world.waiting.is_empty());
+        assert_eq!(world.wallets[&address(&student)].balance, coins(90));
+    }
+
+    #[test]
+    fn blocks_that_miss_the_tip_are_requeued() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        world.waiting = vec![create_wallet(&student, 1)];
+
+        let block = next_block(&mut world, &PublicKey::from(&miner)).unwrap();
+
+        assert!(mine(&mut world, &miner, vec![]));
+
+        world.requeue(block.data);
+
+        assert_eq!(world.waiting.len(), 1);
+        assert!(matches!(world.waiting[0], BlockData::CreateWallet { .. }));
+    }
+
+    // A node following a peer mines with a key that has no wallet, so it leaves its events to the
+    // peer, which relays its block back.
+    #[test]
+    fn nodes_without_a_miner_wallet_leave_events_to_their_peers() {
+        let miner = key();
+        let follower = key();
+        let (sender, receiver) = (key(), key());
+        let mut first = world(&miner);
+
+        assert!(mine(
+            &mut first,
+            &miner,
+            vec![create_wallet(&sender, 1), create_wallet(&receiver, 2)]
+        ));
+
+        let mut second = WorldState::new(first.chain.clone()).unwrap();
+        let payment = paid_transaction(&sender, &receiver, coins(10), coins(1));
+
+        assert!(submit::queue(&mut second, payment.clone()).valid);
+        assert!(next_block(&mut second, &PublicKey::from(&follower)).is_none());
+        assert_eq!(second.waiting.len(), 1);
+
+        // What the relay does.
+        assert!(submit::queue(&mut first, payment).valid);
+
+        let mut block = next_block(&mut first, &PublicKey::from(&miner)).unwrap();
+
+        assert_eq!(block.data.len(), 2);
+        assert!(first.chain.mine_block(&mut block, &miner));
+        assert!(first.add_block(block.clone()));
+        assert!(second.add_block(block));
+
+        let received = coins(109);
+
+        assert!(second.waiting.is_empty());
+        assert_eq!(first.wallets[&address(&receiver)].balance, received);
+        assert_eq!(second.wallets[&address(&receiver)].balance, received);
+    }
+
+    #[test]
+    fn expired_events_are_rejected() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![create_wallet(&student, 1)]));
+
+        let height = world.chain.tip().unwrap().id;
+        let expiry = Some(Expiry::Height(height));
+        let expired = conditional_transaction(&student, &miner, None, expiry);
+
+        assert!(world.is_expired(&expired, height + 1, &now()));
+        assert!(!submit::queue(&mut world, expired.clone()).valid);
+        assert!(!world.add_block(block(&world, &miner, vec![expired.clone()])));
+
+        // Expiring while waiting, such as behind a lock or while the node was busy.
+        let current =
+            conditional_transaction(&student, &miner, None, Some(Expiry::Height(height + 1)));
+
+        world.waiting = vec![expired, current.clone()];
+
+        let mut block = next_block(&mut world, &PublicKey::from(&miner)).unwrap();
+
+        assert_eq!(block.data.len(), 2);
+        assert_eq!(block.data[1].hash(), current.hash());
+        assert!(world.waiting.is_empty());
+        assert!(world.chain.mine_block(&mut block, &miner));
+        assert!(world.add_block(block));
+    }
+}
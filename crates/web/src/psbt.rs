@@ -0,0 +1,146 @@
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use farcoin::PartialTransaction;
+use serde::{Deserialize, Serialize};
+
+use crate::{submit, WorldHandle};
+
+#[derive(Debug, Deserialize)]
+pub struct CombineRequest {
+    psbts: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CombineResponse {
+    valid: bool,
+    message: String,
+    psbt: String,
+    signed: usize,
+    threshold: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitRequest {
+    psbt: String,
+}
+
+pub async fn combine(Json(request): Json<CombineRequest>) -> impl IntoResponse {
+    let mut psbts = vec![];
+
+    for psbt in &request.psbts {
+        let Ok(psbt) = PartialTransaction::try_from(psbt.as_str()) else {
+            return (StatusCode::CREATED, Json(CombineResponse { valid: false, message: "Invalid partially signed transaction!".into(), psbt: String::new(), signed: 0, threshold: 0 }));
+        };
+
+        psbts.push(psbt);
+    }
+
+    let Some((combined, rest)) = psbts.split_first_mut() else {
+        return (StatusCode::CREATED, Json(CombineResponse { valid: false, message: "No partially signed transactions given!".into(), psbt: String::new(), signed: 0, threshold: 0 }));
+    };
+
+    for psbt in rest.iter() {
+        if !combined.combine(psbt) {
+            return (
+                StatusCode::CREATED,
+                Json(CombineResponse {
+                    valid: false,
+                    message: "Partially signed transactions are for different data!".into(),
+                    psbt: String::new(),
+                    signed: 0,
+                    threshold: 0,
+                }),
+            );
+        }
+    }
+
+    (
+        StatusCode::CREATED,
+        Json(CombineResponse {
+            valid: true,
+            message: format!(
+                "{} signatures collected, {} required!",
+                combined.signed(),
+                combined.threshold()
+            ),
+            psbt: String::from(&*combined),
+            signed: combined.signed(),
+            threshold: combined.threshold(),
+        }),
+    )
+}
+
+pub async fn submit(
+    Json(request): Json<SubmitRequest>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(psbt) = PartialTransaction::try_from(request.psbt.as_str()) else {
+        return (StatusCode::CREATED, Json(submit::Response { valid: false, message: "Invalid partially signed transaction!".into() }));
+    };
+
+    let Some(data) = psbt.finalize() else {
+        return (StatusCode::CREATED, Json(submit::Response { valid: false, message: format!("Only {} signatures collected, {} required!", psbt.signed(), psbt.threshold()) }));
+    };
+
+    let Ok(mut world) = world.lock() else {
+        return (StatusCode::CREATED, Json(submit::Response { valid: false, message: "Internal Server Error".into() }));
+    };
+
+    (StatusCode::CREATED, Json(submit::queue(&mut world, data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use farcoin::{Amount, BlockData, PublicKey, Signature};
+
+    use super::*;
+    use crate::test_util::{address, coins, create_wallet, key, mine, now, world};
+
+    // The flow of `transaction --from`, `sign` and `submit` in the CLI, each step handing the
+    // next one the hex encoding.
+    #[test]
+    fn unsigned_transactions_are_signed_offline_and_submitted() {
+        let miner = key();
+        let sender = key();
+        let receiver = key();
+        let mut world = world(&miner);
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![create_wallet(&sender, 1), create_wallet(&receiver, 2)]
+        ));
+
+        let unsigned = PartialTransaction::new(BlockData::Transaction {
+            fee: Amount::ZERO,
+            amount: coins(10),
+            time: now(),
+            sender: PublicKey::from(&sender),
+            receiver: address(&receiver),
+            memo: None,
+            lock: None,
+            expiry: None,
+            signature: Signature::placeholder(),
+        });
+        let unsigned = String::from(&unsigned);
+
+        let mut psbt = PartialTransaction::try_from(unsigned.as_str()).unwrap();
+
+        assert!(psbt.finalize().is_none());
+        assert!(!psbt.sign(&receiver));
+        assert!(psbt.sign(&sender));
+
+        let signed = String::from(&psbt);
+        let data = PartialTransaction::try_from(signed.as_str())
+            .unwrap()
+            .finalize()
+            .unwrap();
+        let before = world.wallets[&address(&receiver)].balance;
+
+        assert!(submit::queue(&mut world, data.clone()).valid);
+        assert!(mine(&mut world, &miner, vec![data]));
+        assert_eq!(
+            world.wallets[&address(&receiver)].balance,
+            before.checked_add(coins(10)).unwrap()
+        );
+    }
+}
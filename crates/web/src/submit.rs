@@ -0,0 +1,56 @@
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use farcoin::{BlockData, UtcDateTime};
+use serde::Serialize;
+
+use crate::{world::WorldState, WorldHandle};
+
+#[derive(Debug, Serialize)]
+pub struct Response {
+    pub valid: bool,
+    pub message: String,
+}
+
+pub async fn post(
+    Json(data): Json<BlockData>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(mut world) = world.lock() else {
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
+    };
+
+    (StatusCode::CREATED, Json(queue(&mut world, data)))
+}
+
+pub fn queue(world: &mut WorldState, data: BlockData) -> Response {
+    let hash = data.hash();
+
+    if world.waiting.iter().any(|waiting| waiting.hash() == hash) {
+        return Response {
+            valid: false,
+            message: "Already submitted!".into(),
+        };
+    }
+
+    let next = world.chain.tip().map_or(0, |tip| tip.id + 1);
+
+    if UtcDateTime::now().is_some_and(|now| world.is_expired(&data, next, &now)) {
+        return Response {
+            valid: false,
+            message: "Transaction has expired!".into(),
+        };
+    }
+
+    if !world.verify_data(&data) {
+        return Response {
+            valid: false,
+            message: "Invalid request!".into(),
+        };
+    }
+
+    world.waiting.push(data);
+
+    Response {
+        valid: true,
+        message: "Successfully submitted!".into(),
+    }
+}
@@ -0,0 +1,59 @@
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use farcoin::Amount;
+use serde::{Deserialize, Serialize};
+
+use crate::WorldHandle;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    height: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    valid: bool,
+    message: String,
+    height: u64,
+    supply: Amount,
+    next_reward: Amount,
+    max_reward_supply: Amount,
+}
+
+// Without a height, reports the supply at the tip of the chain.
+pub async fn post(
+    Json(request): Json<Request>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(world) = world.lock() else {
+        return (StatusCode::CREATED, Json(Response { message: "Internal Server Error".into(), ..Default::default() }));
+    };
+
+    let Some(tip) = world.chain.tip() else {
+        return (StatusCode::CREATED, Json(Response { message: "The chain is empty!".into(), ..Default::default() }));
+    };
+
+    let height = request.height.unwrap_or(tip.id);
+
+    let supply = match request.height {
+        Some(height) => world.chain.supply(height),
+        None => Some(world.supply()),
+    };
+
+    let Some(supply) = supply else {
+        return (StatusCode::CREATED, Json(Response { message: "Block not found!".into(), ..Default::default() }));
+    };
+
+    let params = world.params();
+
+    (
+        StatusCode::CREATED,
+        Json(Response {
+            valid: true,
+            message: format!("{} coins in circulation at block {}", supply, height),
+            height,
+            supply,
+            next_reward: params.reward(height + 1),
+            max_reward_supply: params.max_reward_supply,
+        }),
+    )
+}
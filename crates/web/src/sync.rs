@@ -0,0 +1,476 @@
+use std::{collections::HashSet, time::Duration};
+
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use farcoin::{Block, BlockData, BlockHeader, Hash};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::{world::WorldState, WorldHandle};
+
+const MAX_HEADERS: usize = 500;
+const MAX_BLOCKS: usize = 50;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5000);
+const RETRY_INTERVAL: Duration = Duration::from_millis(10000);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Locator {
+    Height(u64),
+    Hash(Hash),
+    Hashes(Vec<Hash>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeadersRequest {
+    start: Locator,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeadersResponse {
+    known: bool,
+    headers: Vec<BlockHeader>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlocksRequest {
+    hashes: Vec<Hash>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlocksResponse {
+    blocks: Vec<Block>,
+}
+
+pub async fn headers(
+    Json(request): Json<HeadersRequest>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(world) = world.lock() else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(HeadersResponse { known: false, headers: vec![] }));
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(find_headers(&world, &request.start)),
+    )
+}
+
+fn find_headers(world: &WorldState, start: &Locator) -> HeadersResponse {
+    let blocks = world.chain.blocks();
+
+    let start = match start {
+        Locator::Height(height) => blocks.iter().position(|block| block.id >= *height),
+        Locator::Hash(hash) => {
+            let Some(position) = world.chain.position(hash) else {
+                return HeadersResponse {
+                    known: false,
+                    headers: vec![],
+                };
+            };
+
+            Some(position + 1)
+        }
+        Locator::Hashes(hashes) => {
+            let Some(position) = hashes.iter().find_map(|hash| world.chain.position(hash)) else {
+                return HeadersResponse {
+                    known: false,
+                    headers: vec![],
+                };
+            };
+
+            Some(position + 1)
+        }
+    };
+
+    let headers = blocks
+        .iter()
+        .skip(start.unwrap_or(blocks.len()))
+        .take(MAX_HEADERS)
+        .map(Block::header)
+        .collect();
+
+    HeadersResponse {
+        known: true,
+        headers,
+    }
+}
+
+pub async fn blocks(
+    Json(request): Json<BlocksRequest>,
+    Extension(world): Extension<WorldHandle>,
+) -> impl IntoResponse {
+    let Ok(world) = world.lock() else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(BlocksResponse { blocks: vec![] }));
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(find_blocks(&world, &request.hashes)),
+    )
+}
+
+fn find_blocks(world: &WorldState, hashes: &[Hash]) -> BlocksResponse {
+    let blocks = hashes
+        .iter()
+        .take(MAX_BLOCKS)
+        .map_while(|hash| world.chain.find(hash).cloned())
+        .collect();
+
+    BlocksResponse { blocks }
+}
+
+pub async fn sync_worker(handle: WorldHandle, peer: String) {
+    let client = reqwest::Client::new();
+
+    let mut pending = vec![];
+    let mut relayed = HashSet::new();
+
+    loop {
+        if let Err(message) = relay_round(&client, &handle, &peer, &mut relayed).await {
+            println!("Failed to relay events to {}: {}", peer, message);
+        }
+
+        match sync_round(&client, &handle, &peer, &mut pending).await {
+            Ok(0) => sleep(POLL_INTERVAL).await,
+            Ok(count) => println!("Synchronized {} blocks from {}!", count, peer),
+            Err(message) => {
+                println!("Failed to synchronize with {}: {}", peer, message);
+
+                sleep(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+// Passes the events waiting here on to the peer, once each, so that they reach a node that mines
+// them even when this one cannot.
+async fn relay_round(
+    client: &reqwest::Client,
+    handle: &WorldHandle,
+    peer: &str,
+    relayed: &mut HashSet<Hash>,
+) -> Result<(), String> {
+    let waiting = {
+        let Ok(world) = handle.lock() else {
+            return Err("failed to lock world".into());
+        };
+
+        let hashes: HashSet<_> = world.waiting.iter().map(BlockData::hash).collect();
+
+        relayed.retain(|hash| hashes.contains(hash));
+
+        world
+            .waiting
+            .iter()
+            .filter(|data| !relayed.contains(&data.hash()))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    for data in waiting {
+        client
+            .post(format!("{}/submit", peer))
+            .json(&data)
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+
+        relayed.insert(data.hash());
+    }
+
+    Ok(())
+}
+
+async fn sync_round(
+    client: &reqwest::Client,
+    handle: &WorldHandle,
+    peer: &str,
+    pending: &mut Vec<BlockHeader>,
+) -> Result<usize, String> {
+    if pending.is_empty() {
+        let start = {
+            let Ok(world) = handle.lock() else {
+                return Err("failed to lock world".into());
+            };
+
+            match world.chain.tip() {
+                Some(_) => Locator::Hashes(world.chain.locator()),
+                None => Locator::Height(0),
+            }
+        };
+
+        let response: HeadersResponse = client
+            .post(format!("{}/sync/headers", peer))
+            .json(&HeadersRequest { start })
+            .send()
+            .await
+            .map_err(|error| error.to_string())?
+            .json()
+            .await
+            .map_err(|error| error.to_string())?;
+
+        if !response.known {
+            return Err("peer does not share our chain".into());
+        }
+
+        let Ok(world) = handle.lock() else {
+            return Err("failed to lock world".into());
+        };
+
+        queue_headers(&world, pending, response.headers)?;
+    }
+
+    let mut count = 0;
+
+    while !pending.is_empty() {
+        let hashes: Vec<_> = pending
+            .iter()
+            .take(MAX_BLOCKS)
+            .map(|header| header.hash)
+            .collect();
+
+        let requested = hashes.len();
+
+        let response: BlocksResponse = client
+            .post(format!("{}/sync/blocks", peer))
+            .json(&BlocksRequest { hashes })
+            .send()
+            .await
+            .map_err(|error| error.to_string())?
+            .json()
+            .await
+            .map_err(|error| error.to_string())?;
+
+        let Ok(mut world) = handle.lock() else {
+            return Err("failed to lock world".into());
+        };
+
+        count += connect_blocks(&mut world, pending, response.blocks, requested)?;
+    }
+
+    Ok(count)
+}
+
+// The headers must link up with our chain and with each other. Those of blocks we do not have yet
+// are queued to be downloaded.
+fn queue_headers(
+    world: &WorldState,
+    pending: &mut Vec<BlockHeader>,
+    headers: Vec<BlockHeader>,
+) -> Result<(), String> {
+    if headers.len() > MAX_HEADERS {
+        return Err("peer sent more headers than allowed".into());
+    }
+
+    let mut previous = headers
+        .first()
+        .and_then(|header| world.chain.find(&header.previous_hash))
+        .map(|block| block.header());
+
+    for header in headers {
+        let valid = match &previous {
+            Some(previous) => header.follows(previous, world.params()),
+            None => header.is_genesis(),
+        };
+
+        if !valid {
+            pending.clear();
+
+            return Err(format!("peer sent invalid header for block {}", header.id));
+        }
+
+        previous = Some(header.clone());
+
+        if !world.chain.contains(&header.hash) {
+            pending.push(header);
+        }
+    }
+
+    Ok(())
+}
+
+// The blocks must be the first of the pending ones, in order. A peer that sends anything else
+// is not followed any further this round.
+fn connect_blocks(
+    world: &mut WorldState,
+    pending: &mut Vec<BlockHeader>,
+    blocks: Vec<Block>,
+    requested: usize,
+) -> Result<usize, String> {
+    if blocks.is_empty() {
+        pending.clear();
+
+        return Err("peer no longer has the requested blocks".into());
+    }
+
+    if blocks.len() > requested {
+        pending.clear();
+
+        return Err("peer sent more blocks than requested".into());
+    }
+
+    let mut count = 0;
+
+    for block in blocks {
+        if pending.first().map(|header| header.hash) != Some(block.hash) {
+            pending.clear();
+
+            return Err(format!("peer sent unexpected block {}", block.id));
+        }
+
+        let id = block.id;
+
+        if !world.add_block(block) {
+            pending.clear();
+
+            return Err(format!("failed to validate block {}", id));
+        }
+
+        pending.remove(0);
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{block, key, mine, world};
+
+    #[test]
+    fn headers_follow_the_first_known_locator_hash() {
+        let miner = key();
+        let mut world = world(&miner);
+
+        for _ in 0..5 {
+            assert!(mine(&mut world, &miner, vec![]));
+        }
+
+        let blocks = world.chain.blocks();
+        let unknown = Hash::new([1; 32]);
+
+        let response = find_headers(&world, &Locator::Hashes(vec![unknown, blocks[3].hash]));
+
+        assert!(response.known);
+        assert_eq!(
+            response
+                .headers
+                .iter()
+                .map(|header| header.id)
+                .collect::<Vec<_>>(),
+            vec![4, 5, 6]
+        );
+
+        let response = find_headers(&world, &Locator::Height(0));
+
+        assert_eq!(response.headers.len(), blocks.len());
+
+        let response = find_headers(&world, &Locator::Hash(blocks[6].hash));
+
+        assert!(response.known && response.headers.is_empty());
+
+        let response = find_headers(&world, &Locator::Hashes(vec![unknown]));
+
+        assert!(!response.known);
+    }
+
+    #[test]
+    fn blocks_stop_at_the_first_unknown_hash() {
+        let miner = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![]));
+
+        let hashes: Vec<_> = world
+            .chain
+            .blocks()
+            .iter()
+            .map(|block| block.hash)
+            .collect();
+
+        let response = find_blocks(
+            &world,
+            &[hashes[0], hashes[2], Hash::new([1; 32]), hashes[1]],
+        );
+
+        assert_eq!(
+            response
+                .blocks
+                .iter()
+                .map(|block| block.id)
+                .collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+    }
+
+    #[test]
+    fn too_many_blocks_are_rejected() {
+        let miner = key();
+        let mut peer = world(&miner);
+        let mut world = peer.clone();
+
+        assert!(mine(&mut peer, &miner, vec![]));
+        assert!(mine(&mut peer, &miner, vec![]));
+
+        let blocks = peer.chain.blocks()[2..].to_vec();
+        let mut pending = vec![blocks[0].header()];
+
+        assert!(connect_blocks(&mut world, &mut pending, blocks.clone(), 1).is_err());
+        assert!(pending.is_empty());
+        assert_eq!(world.chain.blocks().len(), 2);
+
+        let mut pending: Vec<_> = blocks.iter().map(Block::header).collect();
+
+        assert_eq!(connect_blocks(&mut world, &mut pending, blocks, 2), Ok(2));
+        assert!(pending.is_empty());
+        assert_eq!(
+            world.chain.tip().map(|tip| tip.hash),
+            peer.chain.tip().map(|tip| tip.hash)
+        );
+    }
+
+    #[test]
+    fn headers_are_queued_up_to_the_limit() {
+        let miner = key();
+        let mut peer = world(&miner);
+        let world = peer.clone();
+
+        for _ in 0..3 {
+            assert!(mine(&mut peer, &miner, vec![]));
+        }
+
+        let headers: Vec<_> = peer.chain.blocks()[2..].iter().map(Block::header).collect();
+        let mut pending = vec![];
+
+        let too_many = vec![headers[0].clone(); MAX_HEADERS + 1];
+
+        assert!(queue_headers(&world, &mut pending, too_many).is_err());
+        assert!(pending.is_empty());
+
+        let unlinked = vec![headers[0].clone(), headers[2].clone()];
+
+        assert!(queue_headers(&world, &mut pending, unlinked).is_err());
+        assert!(pending.is_empty());
+
+        assert_eq!(queue_headers(&world, &mut pending, headers.clone()), Ok(()));
+        assert_eq!(
+            pending.iter().map(|header| header.hash).collect::<Vec<_>>(),
+            headers.iter().map(|header| header.hash).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn unexpected_blocks_are_rejected() {
+        let miner = key();
+        let mut world = world(&miner);
+
+        let first = block(&world, &miner, vec![]);
+        let other = block(&world, &miner, vec![]);
+        let mut pending = vec![first.header()];
+
+        assert!(connect_blocks(&mut world, &mut pending, vec![other], 1).is_err());
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use farcoin::{
+    Address, Amount, Block, BlockData, ChainParams, Hash, Network, PrivateKey, PublicKey,
+    Signature, UtcDateTime,
+};
+
+use crate::world::WorldState;
+
+pub fn key() -> PrivateKey {
+    PrivateKey::random(rand::thread_rng())
+}
+
+// A regtest chain whose miner already has a wallet, as created by the node on startup.
+pub fn world(miner: &PrivateKey) -> WorldState {
+    WorldState::new(crate::create_chain(miner, ChainParams::regtest())).expect("valid world state")
+}
+
+pub fn signed(mut data: BlockData, key: &PrivateKey) -> BlockData {
+    assert!(data.sign(key));
+
+    data
+}
+
+pub fn coins(coins: u64) -> Amount {
+    Amount::from_coins(coins).expect("valid amount")
+}
+
+pub fn address(key: &PrivateKey) -> Address {
+    Address::new(Network::Regtest, &PublicKey::from(key))
+}
+
+pub fn create_wallet(key: &PrivateKey, id: u64) -> BlockData {
+    let wallet = BlockData::CreateWallet {
+        id,
+        key: PublicKey::from(key),
+        time: now(),
+        signature: Signature::placeholder(),
+    };
+
+    signed(wallet, key)
+}
+
+pub fn transaction(sender: &PrivateKey, receiver: &PrivateKey, amount: Amount) -> BlockData {
+    paid_transaction(sender, receiver, amount, Amount::ZERO)
+}
+
+pub fn paid_transaction(
+    sender: &PrivateKey,
+    receiver: &PrivateKey,
+    amount: Amount,
+    fee: Amount,
+) -> BlockData {
+    let transaction = BlockData::Transaction {
+        fee,
+        amount,
+        time: now(),
+        sender: PublicKey::from(sender),
+        receiver: address(receiver),
+        memo: None,
+        lock: None,
+        expiry: None,
+        signature: Signature::placeholder(),
+    };
+
+    signed(transaction, sender)
+}
+
+pub fn now() -> UtcDateTime {
+    UtcDateTime::now().expect("current time")
+}
+
+pub fn block(world: &WorldState, miner: &PrivateKey, data: Vec<BlockData>) -> Block {
+    let tip = world.chain.tip().expect("chain with a genesis block");
+
+    let mut block = Block {
+        id: tip.id + 1,
+        nonce: 0,
+        miner: PublicKey::from(miner),
+        time: now(),
+        data,
+        previous_hash: tip.hash,
+        signature: Signature::placeholder(),
+        hash: Hash::zero(),
+    };
+
+    assert!(world.chain.mine_block(&mut block, miner));
+
+    block
+}
+
+pub fn mine(world: &mut WorldState, miner: &PrivateKey, data: Vec<BlockData>) -> bool {
+    let block = block(world, miner, data);
+
+    world.add_block(block)
+}
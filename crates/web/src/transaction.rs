@@ -3,10 +3,10 @@ use axum::{
     response::{Html, IntoResponse},
     Extension, Json,
 };
-use farcoin::{BlockData, Hash, PrivateKey, PublicKey, UtcDateTime};
+use farcoin::{Address, Amount, BlockData, Memo, PrivateKey, PublicKey, Signature, UtcDateTime};
 use serde::{Deserialize, Serialize};
 
-use crate::WorldHandle;
+use crate::{world::Owner, WorldHandle};
 
 pub async fn get() -> Html<&'static str> {
     Html(include_str!("../../../frontend/transaction.html"))
@@ -19,6 +19,11 @@ pub struct Request {
     private_key: String,
     amount: String,
     fee: String,
+    #[serde(default)]
+    memo: String,
+    // Encrypts the memo so that only the receiver can read it.
+    #[serde(default)]
+    encrypt_memo: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -35,12 +40,13 @@ pub async fn post(
         return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
     };
 
-    let receiver_key = if let Ok(receiver_key) = PublicKey::try_from(request.receiver_key.as_str())
-    {
-        receiver_key
+    let receiver = if let Ok(receiver) = Address::try_from(request.receiver_key.as_str()) {
+        receiver
+    } else if let Ok(receiver_key) = PublicKey::try_from(request.receiver_key.as_str()) {
+        world.address(&receiver_key)
     } else if let Ok(student_id) = request.receiver_key.parse::<u64>() {
-        if let Some(receiver_key) = world.wallet_ids.get(&student_id) {
-            receiver_key.clone()
+        if let Some(receiver) = world.wallet_ids.get(&student_id) {
+            *receiver
         } else {
             return (
                 StatusCode::CREATED,
@@ -55,12 +61,12 @@ pub async fn post(
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
-                message: "Invalid receiver key!".into(),
+                message: "Invalid receiver address or key!".into(),
             }),
         );
     };
 
-    if !world.wallets.contains_key(&receiver_key) {
+    if !world.wallets.contains_key(&receiver) {
         return (
             StatusCode::CREATED,
             Json(Response {
@@ -70,11 +76,13 @@ pub async fn post(
         );
     }
 
-    let public_key = if let Ok(public_key) = PublicKey::try_from(request.public_key.as_str()) {
-        public_key
+    let address = if let Ok(address) = Address::try_from(request.public_key.as_str()) {
+        address
+    } else if let Ok(public_key) = PublicKey::try_from(request.public_key.as_str()) {
+        world.address(&public_key)
     } else if let Ok(student_id) = request.public_key.parse::<u64>() {
-        if let Some(public_key) = world.wallet_ids.get(&student_id) {
-            public_key.clone()
+        if let Some(address) = world.wallet_ids.get(&student_id) {
+            *address
         } else {
             return (
                 StatusCode::CREATED,
@@ -89,70 +97,127 @@ pub async fn post(
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
-                message: "Invalid public key!".into(),
+                message: "Invalid address or public key!".into(),
             }),
         );
     };
 
-    if !world.wallets.contains_key(&public_key) {
+    let Some(wallet) = world.wallets.get(&address) else {
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Wallet does not exist!".into() }));
+    };
+
+    let Owner::Key(public_key) = wallet.owner.clone() else {
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Multisig wallets must sign transactions with farcoin-cli!".into() }));
+    };
+
+    let private_key = match PrivateKey::try_from(request.private_key.as_str()) {
+        Ok(private_key) => private_key,
+        Err(error) => {
+            return (
+                StatusCode::CREATED,
+                Json(Response {
+                    valid: false,
+                    message: format!("Invalid private key: {}!", error),
+                }),
+            );
+        }
+    };
+
+    let private_key = private_key.with_scheme(public_key.scheme());
+
+    if public_key != PublicKey::from(&private_key) {
         return (
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
-                message: "Wallet does not exist!".into(),
+                message: "Invalid public key or student ID!".into(),
             }),
         );
     }
 
-    let Ok(private_key) = PrivateKey::try_from(request.private_key.as_str()) else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid private key!".into() }));
+    let amount = match request.amount.trim().parse::<Amount>() {
+        Ok(amount) => amount,
+        Err(error) => {
+            return (
+                StatusCode::CREATED,
+                Json(Response {
+                    valid: false,
+                    message: format!("Invalid amount: {}!", error),
+                }),
+            );
+        }
     };
 
-    if public_key != PublicKey::from(&private_key) {
+    if amount.is_zero() {
         return (
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
-                message: "Invalid public key or student ID!".into(),
+                message: "Amount must be greater than zero!".into(),
             }),
         );
     }
 
-    let Ok(amount) = request.amount.parse::<u64>() else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid amount!".into() }));
+    let fee = match request.fee.trim().parse::<Amount>() {
+        Ok(fee) => fee,
+        Err(error) => {
+            return (
+                StatusCode::CREATED,
+                Json(Response {
+                    valid: false,
+                    message: format!("Invalid fee: {}!", error),
+                }),
+            );
+        }
     };
 
-    if amount == 0 {
+    if !world.params().check_fee(fee, amount) {
         return (
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
-                message: "Amount must be greater than zero!".into(),
+                message: format!(
+                    "Fee must be at least {} and less than the amount!",
+                    world.params().min_fee
+                ),
             }),
         );
     }
 
-    let Ok(fee) = request.fee.parse::<u64>() else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid fee!".into() }));
+    let memo = match request.memo.trim() {
+        "" => None,
+        memo => Some(Memo::Text(memo.to_string())),
     };
 
-    if fee >= amount {
+    if !world.params().check_memo(memo.as_ref()) {
         return (
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
-                message: "Fee must be less than the amount!".into(),
+                message: format!(
+                    "Memo must be at most {} bytes on a single line!",
+                    world.params().max_memo_len
+                ),
             }),
         );
     }
 
-    let Some(time) = UtcDateTime::now() else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
-    };
-
     let mut rng = rand::thread_rng();
 
-    let Some(signature) = private_key.sign_with_rng(&mut rng, &Hash::empty()) else {
+    let memo = match memo {
+        Some(Memo::Text(text)) if request.encrypt_memo => {
+            let receiver_owner = world.wallets.get(&receiver).map(|wallet| &wallet.owner);
+
+            let Some(Owner::Key(receiver_key)) = receiver_owner else {
+                return (StatusCode::CREATED, Json(Response { valid: false, message: "Memos can only be encrypted to wallets with a single key!".into() }));
+            };
+
+            Some(Memo::encrypt(&mut rng, receiver_key, &text))
+        }
+        memo => memo,
+    };
+
+    let Some(time) = UtcDateTime::now() else {
         return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
     };
 
@@ -161,8 +226,11 @@ pub async fn post(
         amount,
         time,
         sender: public_key.clone(),
-        receiver: receiver_key.clone(),
-        signature,
+        receiver,
+        memo,
+        lock: None,
+        expiry: None,
+        signature: Signature::placeholder(),
     };
 
     let transaction_hash = transaction.hash();
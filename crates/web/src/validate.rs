@@ -22,14 +22,18 @@ pub async fn post(Extension(world): Extension<WorldHandle>) -> impl IntoResponse
         return (StatusCode::CREATED, Json(Response { status: "Internal Server Error".to_string(), chain_data: "".to_string() }));
     };
 
+    let status = if !world.chain.verify_links() {
+        "Invalid".to_string()
+    } else if let Err(item) = world.chain.verify_signatures() {
+        format!("Invalid signature for {}", item)
+    } else {
+        "Valid".to_string()
+    };
+
     (
         StatusCode::CREATED,
         Json(Response {
-            status: world
-                .chain
-                .validate()
-                .then_some("Valid".to_string())
-                .unwrap_or("Invalid".to_string()),
+            status,
             chain_data: serde_json::to_string_pretty(&world.chain)
                 .unwrap_or("Internal Server Error".to_string()),
         }),
@@ -3,7 +3,7 @@ use axum::{
     response::{Html, IntoResponse},
     Extension, Json,
 };
-use farcoin::{BlockData, Hash, PrivateKey, PublicKey, UtcDateTime};
+use farcoin::{BlockData, PrivateKey, PublicKey, Signature, UtcDateTime};
 use serde::{Deserialize, Serialize};
 
 use crate::WorldHandle;
@@ -23,6 +23,7 @@ pub struct Request {
 struct Response {
     valid: bool,
     message: String,
+    address: String,
 }
 
 pub async fn post(
@@ -30,11 +31,11 @@ pub async fn post(
     Extension(world): Extension<WorldHandle>,
 ) -> impl IntoResponse {
     let Ok(mut world) = world.lock() else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into(), address: String::new() }));
     };
 
     let Ok(student_id) = request.student_id.parse::<u64>() else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid student ID!".into() }));
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid student ID!".into(), address: String::new() }));
     };
 
     if world.wallet_ids.contains_key(&student_id) {
@@ -43,63 +44,86 @@ pub async fn post(
             Json(Response {
                 valid: false,
                 message: "Student ID already in use!".into(),
+                address: String::new(),
             }),
         );
     }
 
-    let Ok(public_key) = PublicKey::try_from(request.public_key.as_str()) else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid public key!".into() }));
+    let public_key = match PublicKey::try_from(request.public_key.as_str()) {
+        Ok(public_key) => public_key,
+        Err(error) => {
+            return (
+                StatusCode::CREATED,
+                Json(Response {
+                    valid: false,
+                    message: format!("Invalid public key: {}!", error),
+                    address: String::new(),
+                }),
+            );
+        }
     };
 
-    if world.wallets.contains_key(&public_key) {
+    let address = world.address(&public_key);
+
+    if world.wallets.contains_key(&address) {
         return (
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
                 message: "Public key already in use!".into(),
+                address: String::new(),
             }),
         );
     }
 
-    let Ok(private_key) = PrivateKey::try_from(request.private_key.as_str()) else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Invalid private key!".into() }));
+    let private_key = match PrivateKey::try_from(request.private_key.as_str()) {
+        Ok(private_key) => private_key,
+        Err(error) => {
+            return (
+                StatusCode::CREATED,
+                Json(Response {
+                    valid: false,
+                    message: format!("Invalid private key: {}!", error),
+                    address: String::new(),
+                }),
+            );
+        }
     };
 
+    let private_key = private_key.with_scheme(public_key.scheme());
+
     if public_key != PublicKey::from(&private_key) {
         return (
             StatusCode::CREATED,
             Json(Response {
                 valid: false,
                 message: "Invalid public key!".into(),
+                address: String::new(),
             }),
         );
     }
 
     let Some(time) = UtcDateTime::now() else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into(), address: String::new() }));
     };
 
     let mut rng = rand::thread_rng();
 
-    let Some(signature) = private_key.sign_with_rng(&mut rng, &Hash::empty()) else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
-    };
-
     let mut wallet = BlockData::CreateWallet {
         id: student_id,
         key: public_key,
         time,
-        signature,
+        signature: Signature::placeholder(),
     };
 
     let wallet_hash = wallet.hash();
 
     let BlockData::CreateWallet { ref mut signature, .. } = wallet else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into(), address: String::new() }));
     };
 
     let Some(wallet_signature) = private_key.sign_with_rng(&mut rng, &wallet_hash) else {
-        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into() }));
+        return (StatusCode::CREATED, Json(Response { valid: false, message: "Internal Server Error".into(), address: String::new() }));
     };
 
     *signature = wallet_signature;
@@ -110,6 +134,7 @@ pub async fn post(
             Json(Response {
                 valid: false,
                 message: "Invalid request!".into(),
+                address: String::new(),
             }),
         );
     }
@@ -121,6 +146,7 @@ pub async fn post(
         Json(Response {
             valid: true,
             message: "Successfully created new wallet!".into(),
+            address: String::from(&address),
         }),
     )
 }
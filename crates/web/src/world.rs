@@ -1,59 +1,226 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use farcoin::{Block, BlockChain, BlockData, PublicKey, UtcDateTime};
+use farcoin::{
+    Address, Amount, Block, BlockChain, BlockData, ChainParams, Hash, Lock, Memo, Multisig,
+    Network, Output, Preimage, PublicKey, SignatureBatch, UtcDateTime,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Transaction {
     Send {
-        fee: u64,
-        miner: PublicKey,
-        amount: u64,
-        receiver: PublicKey,
+        fee: Amount,
+        miner: Address,
+        amount: Amount,
+        receiver: Address,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<Memo>,
+    },
+    BatchSend {
+        fee: Amount,
+        miner: Address,
+        outputs: Vec<Output>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<Memo>,
     },
     Receive {
-        amount: u64,
-        sender: PublicKey,
+        amount: Amount,
+        sender: Address,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<Memo>,
     },
     CollectFee {
-        fee: u64,
-        sender: PublicKey,
+        fee: Amount,
+        sender: Address,
+    },
+    Reward {
+        amount: Amount,
+        block: u64,
+    },
+    LockHtlc {
+        fee: Amount,
+        miner: Address,
+        amount: Amount,
+        receiver: Address,
+        htlc: Hash,
+    },
+    ClaimHtlc {
+        amount: Amount,
+        sender: Address,
+        htlc: Hash,
     },
+    RefundHtlc {
+        amount: Amount,
+        htlc: Hash,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Owner {
+    Key(PublicKey),
+    Multisig(Multisig),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Wallet {
     pub id: u64,
-    pub balance: u64,
+    pub owner: Owner,
+    pub balance: Amount,
     pub creation_time: UtcDateTime,
     pub transaction_history: HashMap<UtcDateTime, Transaction>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HtlcStatus {
+    Open,
+    Claimed(Preimage),
+    Refunded,
+}
+
+// Settled contracts are kept so that the other side of a swap can look up the preimage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Htlc {
+    pub sender: Address,
+    pub receiver: Address,
+    pub amount: Amount,
+    pub hash_lock: Hash,
+    pub timeout: Lock,
+    pub status: HtlcStatus,
+}
+
+// What connecting a block changed, to be put back when it is disconnected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Undo {
+    wallets: Vec<WalletUndo>,
+    wallet_ids: Vec<(u64, Option<Address>)>,
+    htlcs: Vec<(Hash, Option<Htlc>)>,
+    supply: Amount,
+}
+
+// A block only changes the balance of a wallet and its history at the times of the block's data,
+// so that is all that is kept rather than the whole wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WalletUndo {
+    address: Address,
+    // None if the wallet did not exist before the block.
+    balance: Option<Amount>,
+    history: Vec<(UtcDateTime, Option<Transaction>)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorldState {
     pub chain: BlockChain,
     pub waiting: Vec<BlockData>,
-    pub wallets: HashMap<PublicKey, Wallet>,
-    pub wallet_ids: HashMap<u64, PublicKey>,
+    pub wallets: HashMap<Address, Wallet>,
+    pub wallet_ids: HashMap<u64, Address>,
+    pub htlcs: HashMap<Hash, Htlc>,
+    // Coins issued by the blocks of the chain so far, kept up to date as blocks are connected.
+    supply: Amount,
+    // One entry per block down to the deepest possible reorganization.
+    undo: VecDeque<Undo>,
 }
 
 impl WorldState {
+    pub fn params(&self) -> &ChainParams {
+        self.chain.params()
+    }
+
+    pub fn network(&self) -> Network {
+        self.params().network
+    }
+
+    pub fn address(&self, key: &PublicKey) -> Address {
+        Address::new(self.network(), key)
+    }
+
+    pub fn multisig_address(&self, multisig: &Multisig) -> Address {
+        Address::multisig(self.network(), multisig)
+    }
+
+    // Refunds also wait for the timeout of their contract.
+    pub fn is_unlocked(&self, data: &BlockData, id: u64, time: &UtcDateTime) -> bool {
+        match data {
+            BlockData::RefundHtlc { htlc, .. } => self
+                .htlcs
+                .get(htlc)
+                .is_none_or(|htlc| htlc.timeout.is_unlocked(id, time)),
+            _ => data.is_unlocked(id, time),
+        }
+    }
+
+    // Claims also expire at the timeout of their contract, and contracts once they could be refunded
+    // straight away.
+    pub fn is_expired(&self, data: &BlockData, id: u64, time: &UtcDateTime) -> bool {
+        match data {
+            BlockData::CreateHtlc { timeout, .. } => timeout.is_unlocked(id, time),
+            BlockData::ClaimHtlc { htlc, .. } => self
+                .htlcs
+                .get(htlc)
+                .is_some_and(|htlc| htlc.timeout.is_unlocked(id, time)),
+            _ => data.is_expired(id, time),
+        }
+    }
+
+    // Puts the data of a block that did not make it into the chain back into the queue, unless it
+    // is already waiting or no longer valid.
+    pub fn requeue(&mut self, data: Vec<BlockData>) {
+        for data in data {
+            if matches!(data, BlockData::Reward { .. }) {
+                continue;
+            }
+
+            let hash = data.hash();
+
+            if !self.waiting.iter().any(|waiting| waiting.hash() == hash) && self.verify_data(&data)
+            {
+                self.waiting.push(data);
+            }
+        }
+    }
+
     pub fn verify_data(&self, data: &BlockData) -> bool {
+        self.check_data(data) && data.verify()
+    }
+
+    // Data whose wallet, contract or history entry is already taken can never go into a block,
+    // unlike data that only waits on coins or wallets that may still arrive.
+    pub fn is_applied(&self, data: &BlockData) -> bool {
+        let taken = |address: Address, time: &UtcDateTime| {
+            self.wallets
+                .get(&address)
+                .is_some_and(|wallet| wallet.transaction_history.contains_key(time))
+        };
+
         match data {
-            farcoin::BlockData::CreateWallet {
-                id, key, signature, ..
-            } => {
-                if self.wallet_ids.contains_key(&id) {
-                    return false;
-                }
+            BlockData::CreateWallet { id, key, .. } => {
+                !self.check_new_wallet(*id, &self.address(key))
+            }
+            BlockData::CreateMultisigWallet { id, owners, .. } => {
+                !self.check_new_wallet(*id, &self.multisig_address(owners))
+            }
+            BlockData::Transaction { time, sender, .. }
+            | BlockData::BatchTransaction { time, sender, .. }
+            | BlockData::CreateHtlc { time, sender, .. } => taken(self.address(sender), time),
+            BlockData::MultisigTransaction { time, sender, .. } => {
+                taken(self.multisig_address(sender), time)
+            }
+            BlockData::ClaimHtlc { htlc, .. } | BlockData::RefundHtlc { htlc, .. } => self
+                .htlcs
+                .get(htlc)
+                .is_some_and(|htlc| htlc.status != HtlcStatus::Open),
+            BlockData::Reward { .. } => true,
+        }
+    }
 
-                if self.wallets.contains_key(&key) {
-                    return false;
-                }
+    // Checks everything about the data except its signatures.
+    fn check_data(&self, data: &BlockData) -> bool {
+        if !self.params().check_memo(data.memo()) {
+            return false;
+        }
 
-                if !key.verify(&data.hash(), signature) {
-                    return false;
-                }
+        match data {
+            farcoin::BlockData::CreateWallet { id, key, .. } => {
+                self.check_new_wallet(*id, &self.address(key))
             }
             farcoin::BlockData::Transaction {
                 fee,
@@ -61,75 +228,198 @@ impl WorldState {
                 time,
                 sender,
                 receiver,
-                signature,
+                ..
+            } => self.check_transfer(*fee, *amount, time, &self.address(sender), receiver),
+            farcoin::BlockData::BatchTransaction {
+                fee,
+                time,
+                sender,
+                outputs,
+                ..
+            } => self.check_batch(*fee, outputs, time, &self.address(sender)),
+            farcoin::BlockData::CreateMultisigWallet {
+                id,
+                owners,
+                signatures,
+                ..
             } => {
-                if *amount == 0 {
+                if !owners.is_valid() || !owners.is_satisfied(signatures) {
                     return false;
                 }
 
-                if fee >= amount {
+                self.check_new_wallet(*id, &self.multisig_address(owners))
+            }
+            farcoin::BlockData::MultisigTransaction {
+                fee,
+                amount,
+                time,
+                sender,
+                receiver,
+                signatures,
+                ..
+            } => {
+                if !sender.is_valid() || !sender.is_satisfied(signatures) {
                     return false;
                 }
 
-                if !self.wallets.contains_key(&sender) {
-                    return false;
-                }
+                let sender = self.multisig_address(sender);
 
-                if !self.wallets.contains_key(&receiver) {
+                self.check_transfer(*fee, *amount, time, &sender, receiver)
+            }
+            farcoin::BlockData::CreateHtlc {
+                fee,
+                amount,
+                time,
+                sender,
+                receiver,
+                ..
+            } => {
+                !self.htlcs.contains_key(&data.hash())
+                    && self.check_transfer(*fee, *amount, time, &self.address(sender), receiver)
+            }
+            farcoin::BlockData::ClaimHtlc {
+                htlc,
+                preimage,
+                time,
+                receiver,
+                ..
+            } => {
+                let Some(htlc) = self.htlcs.get(htlc) else {
                     return false;
-                }
+                };
 
-                if !sender.verify(&data.hash(), signature) {
-                    return false;
-                }
+                let receiver = self.address(receiver);
 
-                let Some(sender_wallet) = self.wallets.get(&sender) else {
+                htlc.status == HtlcStatus::Open
+                    && htlc.receiver == receiver
+                    && htlc.hash_lock == preimage.hash_lock()
+                    && self.check_receiver(&receiver, time)
+            }
+            farcoin::BlockData::RefundHtlc {
+                htlc, time, sender, ..
+            } => {
+                let Some(htlc) = self.htlcs.get(htlc) else {
                     return false;
                 };
 
-                if sender_wallet.balance < *amount {
-                    return false;
-                }
+                let sender = self.address(sender);
 
-                if sender_wallet.transaction_history.contains_key(&time) {
-                    return false;
-                }
+                htlc.status == HtlcStatus::Open
+                    && htlc.sender == sender
+                    && self.check_receiver(&sender, time)
+            }
+            farcoin::BlockData::Reward { receiver, .. } => {
+                receiver.network() == self.network() && self.wallets.contains_key(receiver)
+            }
+        }
+    }
 
-                let Some(receiver_wallet) = self.wallets.get(&receiver) else {
-                    return false;
-                };
+    fn check_new_wallet(&self, id: u64, address: &Address) -> bool {
+        !self.wallet_ids.contains_key(&id) && !self.wallets.contains_key(address)
+    }
 
-                if receiver_wallet.transaction_history.contains_key(&time) {
-                    return false;
-                }
-            }
+    fn check_transfer(
+        &self,
+        fee: Amount,
+        amount: Amount,
+        time: &UtcDateTime,
+        sender: &Address,
+        receiver: &Address,
+    ) -> bool {
+        if amount.is_zero() {
+            return false;
         }
 
-        true
+        if !self.params().check_fee(fee, amount) {
+            return false;
+        }
+
+        self.check_sender(sender, amount, time) && self.check_receiver(receiver, time)
+    }
+
+    // All outputs must be able to go through for any of them to.
+    fn check_batch(
+        &self,
+        fee: Amount,
+        outputs: &[Output],
+        time: &UtcDateTime,
+        sender: &Address,
+    ) -> bool {
+        if outputs.is_empty() || outputs.len() > self.params().max_batch_outputs {
+            return false;
+        }
+
+        if outputs.iter().any(|output| output.amount.is_zero()) {
+            return false;
+        }
+
+        let Some(total) = Amount::checked_sum(outputs.iter().map(|output| output.amount)) else {
+            return false;
+        };
+
+        if !self.params().check_batch_fee(fee) {
+            return false;
+        }
+
+        let Some(spent) = total.checked_add(fee) else {
+            return false;
+        };
+
+        // Each wallet keeps one history entry per time, so every receiver may only appear once.
+        let mut receivers = HashSet::from([*sender]);
+
+        if !outputs
+            .iter()
+            .all(|output| receivers.insert(output.receiver))
+        {
+            return false;
+        }
+
+        self.check_sender(sender, spent, time)
+            && outputs
+                .iter()
+                .all(|output| self.check_receiver(&output.receiver, time))
+    }
+
+    fn check_sender(&self, sender: &Address, amount: Amount, time: &UtcDateTime) -> bool {
+        let Some(sender_wallet) = self.wallets.get(sender) else {
+            return false;
+        };
+
+        sender_wallet.balance >= amount && !sender_wallet.transaction_history.contains_key(time)
+    }
+
+    fn check_receiver(&self, receiver: &Address, time: &UtcDateTime) -> bool {
+        if receiver.network() != self.network() {
+            return false;
+        }
+
+        let Some(receiver_wallet) = self.wallets.get(receiver) else {
+            return false;
+        };
+
+        !receiver_wallet.transaction_history.contains_key(time)
     }
 
+    // Signatures are checked for the whole block at once before its data is processed.
     pub fn process_data(&mut self, data: &BlockData, block: &Block) -> bool {
-        if !self.verify_data(data) {
+        if !self.is_unlocked(data, block.id, &block.time)
+            || self.is_expired(data, block.id, &block.time)
+        {
+            return false;
+        }
+
+        if !self.check_data(data) {
             return false;
         }
 
         match data {
             farcoin::BlockData::CreateWallet { id, key, time, .. } => {
-                if time >= &block.time {
-                    return false;
-                }
+                let address = self.address(key);
 
-                self.wallets.insert(
-                    key.clone(),
-                    Wallet {
-                        id: *id,
-                        balance: 100,
-                        creation_time: time.clone(),
-                        transaction_history: HashMap::new(),
-                    },
-                );
+                let grant = self.params().wallet_grant;
 
-                self.wallet_ids.insert(*id, key.clone());
+                self.create_wallet(*id, address, Owner::Key(key.clone()), grant, time, block)
             }
             farcoin::BlockData::Transaction {
                 fee,
@@ -137,99 +427,1363 @@ impl WorldState {
                 time,
                 sender,
                 receiver,
+                memo,
                 ..
             } => {
-                if time >= &block.time {
-                    return false;
-                }
+                let sender = self.address(sender);
 
-                if !self.wallets.contains_key(&block.miner) {
-                    return false;
-                }
-
-                let Some(sender_wallet) = self.wallets.get_mut(&sender) else {
-                    return false;
+                let output = Output {
+                    receiver: *receiver,
+                    amount: *amount,
                 };
 
-                sender_wallet.balance -= amount;
-
-                sender_wallet.transaction_history.insert(
-                    time.clone(),
-                    Transaction::Send {
-                        fee: *fee,
-                        miner: block.miner.clone(),
-                        amount: *amount,
-                        receiver: receiver.clone(),
-                    },
-                );
+                self.transfer(*fee, output, time, sender, memo, block)
+            }
+            farcoin::BlockData::BatchTransaction {
+                fee,
+                time,
+                sender,
+                outputs,
+                memo,
+                ..
+            } => {
+                let sender = self.address(sender);
 
-                let Some(receiver_wallet) = self.wallets.get_mut(&receiver) else {
-                    return false;
-                };
+                self.batch_transfer(*fee, outputs, time, sender, memo, block)
+            }
+            // Multisig wallets belong to groups rather than students, so they start out empty.
+            farcoin::BlockData::CreateMultisigWallet {
+                id, owners, time, ..
+            } => {
+                let address = self.multisig_address(owners);
 
-                receiver_wallet.balance += amount - fee;
+                self.create_wallet(
+                    *id,
+                    address,
+                    Owner::Multisig(owners.clone()),
+                    Amount::ZERO,
+                    time,
+                    block,
+                )
+            }
+            farcoin::BlockData::MultisigTransaction {
+                fee,
+                amount,
+                time,
+                sender,
+                receiver,
+                memo,
+                ..
+            } => {
+                let sender = self.multisig_address(sender);
 
-                receiver_wallet.transaction_history.insert(
-                    time.clone(),
-                    Transaction::Receive {
-                        amount: amount - fee,
-                        sender: sender.clone(),
-                    },
-                );
+                let output = Output {
+                    receiver: *receiver,
+                    amount: *amount,
+                };
 
-                let Some(miner_wallet) = self.wallets.get_mut(&block.miner) else {
+                self.transfer(*fee, output, time, sender, memo, block)
+            }
+            farcoin::BlockData::CreateHtlc {
+                fee,
+                amount,
+                time,
+                sender,
+                receiver,
+                hash_lock,
+                timeout,
+                ..
+            } => {
+                let Some(locked) = amount.checked_sub(*fee) else {
                     return false;
                 };
 
-                miner_wallet.balance += fee;
+                let htlc = Htlc {
+                    sender: self.address(sender),
+                    receiver: *receiver,
+                    amount: locked,
+                    hash_lock: *hash_lock,
+                    timeout: timeout.clone(),
+                    status: HtlcStatus::Open,
+                };
 
-                miner_wallet.transaction_history.insert(
-                    time.clone(),
-                    Transaction::CollectFee {
-                        fee: *fee,
-                        sender: sender.clone(),
-                    },
-                );
+                self.lock_htlc(data.hash(), *fee, time, htlc, block)
+            }
+            farcoin::BlockData::ClaimHtlc {
+                htlc,
+                preimage,
+                time,
+                ..
+            } => self.settle_htlc(*htlc, Some(*preimage), time, block),
+            farcoin::BlockData::RefundHtlc { htlc, time, .. } => {
+                self.settle_htlc(*htlc, None, time, block)
             }
+            farcoin::BlockData::Reward { amount, receiver } => {
+                self.reward(*amount, receiver, block)
+            }
+        }
+    }
+
+    fn create_wallet(
+        &mut self,
+        id: u64,
+        address: Address,
+        owner: Owner,
+        balance: Amount,
+        time: &UtcDateTime,
+        block: &Block,
+    ) -> bool {
+        if time >= &block.time {
+            return false;
         }
 
+        self.wallets.insert(
+            address,
+            Wallet {
+                id,
+                owner,
+                balance,
+                creation_time: time.clone(),
+                transaction_history: HashMap::new(),
+            },
+        );
+
+        self.wallet_ids.insert(id, address);
+
         true
     }
 
-    pub fn add_block(&mut self, block: Block) -> bool {
-        for data in &block.data {
-            if !self.process_data(data, &block) {
-                return false;
-            }
+    // The receiver gets the amount of the output minus the fee.
+    fn transfer(
+        &mut self,
+        fee: Amount,
+        Output { receiver, amount }: Output,
+        time: &UtcDateTime,
+        sender: Address,
+        memo: &Option<Memo>,
+        block: &Block,
+    ) -> bool {
+        if time >= &block.time {
+            return false;
         }
 
-        self.chain.add_block(block);
+        let Some(received) = amount.checked_sub(fee) else {
+            return false;
+        };
+
+        let miner = self.address(&block.miner);
+        let fee = self.payable_fee(fee, &miner);
+
+        let Some(spent) = received.checked_add(fee) else {
+            return false;
+        };
+
+        let Some(sender_wallet) = self.wallets.get_mut(&sender) else {
+            return false;
+        };
+
+        let Some(balance) = sender_wallet.balance.checked_sub(spent) else {
+            return false;
+        };
+
+        sender_wallet.balance = balance;
+
+        sender_wallet.transaction_history.insert(
+            time.clone(),
+            Transaction::Send {
+                fee,
+                miner,
+                amount: spent,
+                receiver,
+                memo: memo.clone(),
+            },
+        );
+
+        let Some(receiver_wallet) = self.wallets.get_mut(&receiver) else {
+            return false;
+        };
+
+        let Some(balance) = receiver_wallet.balance.checked_add(received) else {
+            return false;
+        };
 
-        self.chain.validate()
+        receiver_wallet.balance = balance;
+
+        receiver_wallet.transaction_history.insert(
+            time.clone(),
+            Transaction::Receive {
+                amount: received,
+                sender,
+                memo: memo.clone(),
+            },
+        );
+
+        self.collect_fee(fee, sender, time, &miner)
     }
 
-    pub fn new(chain: BlockChain) -> Option<Self> {
-        if !chain.validate() {
-            return None;
+    fn batch_transfer(
+        &mut self,
+        fee: Amount,
+        outputs: &[Output],
+        time: &UtcDateTime,
+        sender: Address,
+        memo: &Option<Memo>,
+        block: &Block,
+    ) -> bool {
+        if time >= &block.time {
+            return false;
         }
 
-        let mut world = Self {
-            chain: BlockChain::new(),
-            waiting: vec![],
-            wallets: HashMap::new(),
-            wallet_ids: HashMap::new(),
+        let miner = self.address(&block.miner);
+        let fee = self.payable_fee(fee, &miner);
+
+        let Some(spent) = Amount::checked_sum(outputs.iter().map(|output| output.amount))
+            .and_then(|total| total.checked_add(fee))
+        else {
+            return false;
         };
 
-        for block in chain.blocks() {
-            for data in &block.data {
-                if !world.process_data(data, block) {
-                    return None;
-                }
-            }
-        }
+        let Some(sender_wallet) = self.wallets.get_mut(&sender) else {
+            return false;
+        };
+
+        let Some(balance) = sender_wallet.balance.checked_sub(spent) else {
+            return false;
+        };
 
-        world.chain = chain;
+        sender_wallet.balance = balance;
 
-        Some(world)
+        sender_wallet.transaction_history.insert(
+            time.clone(),
+            Transaction::BatchSend {
+                fee,
+                miner,
+                outputs: outputs.to_vec(),
+                memo: memo.clone(),
+            },
+        );
+
+        for output in outputs {
+            let Some(receiver_wallet) = self.wallets.get_mut(&output.receiver) else {
+                return false;
+            };
+
+            let Some(balance) = receiver_wallet.balance.checked_add(output.amount) else {
+                return false;
+            };
+
+            receiver_wallet.balance = balance;
+
+            receiver_wallet.transaction_history.insert(
+                time.clone(),
+                Transaction::Receive {
+                    amount: output.amount,
+                    sender,
+                    memo: memo.clone(),
+                },
+            );
+        }
+
+        self.collect_fee(fee, sender, time, &miner)
+    }
+
+    fn lock_htlc(
+        &mut self,
+        id: Hash,
+        fee: Amount,
+        time: &UtcDateTime,
+        htlc: Htlc,
+        block: &Block,
+    ) -> bool {
+        if time >= &block.time {
+            return false;
+        }
+
+        let miner = self.address(&block.miner);
+        let fee = self.payable_fee(fee, &miner);
+
+        let Some(amount) = htlc.amount.checked_add(fee) else {
+            return false;
+        };
+
+        let Some(sender_wallet) = self.wallets.get_mut(&htlc.sender) else {
+            return false;
+        };
+
+        let Some(balance) = sender_wallet.balance.checked_sub(amount) else {
+            return false;
+        };
+
+        sender_wallet.balance = balance;
+
+        sender_wallet.transaction_history.insert(
+            time.clone(),
+            Transaction::LockHtlc {
+                fee,
+                miner,
+                amount,
+                receiver: htlc.receiver,
+                htlc: id,
+            },
+        );
+
+        if !self.collect_fee(fee, htlc.sender, time, &miner) {
+            return false;
+        }
+
+        self.htlcs.insert(id, htlc);
+
+        true
+    }
+
+    // A miner without a wallet has nowhere to collect fees, so they go unpaid and the sender is
+    // only charged what reaches the receiver.
+    fn payable_fee(&self, fee: Amount, miner: &Address) -> Amount {
+        if self.wallets.contains_key(miner) {
+            fee
+        } else {
+            Amount::ZERO
+        }
+    }
+
+    fn collect_fee(
+        &mut self,
+        fee: Amount,
+        sender: Address,
+        time: &UtcDateTime,
+        miner: &Address,
+    ) -> bool {
+        let Some(miner_wallet) = self.wallets.get_mut(miner) else {
+            return fee.is_zero();
+        };
+
+        let Some(balance) = miner_wallet.balance.checked_add(fee) else {
+            return false;
+        };
+
+        miner_wallet.balance = balance;
+
+        miner_wallet
+            .transaction_history
+            .insert(time.clone(), Transaction::CollectFee { fee, sender });
+
+        true
+    }
+
+    // Pays an open contract out to its receiver given the preimage, or back to its sender without.
+    fn settle_htlc(
+        &mut self,
+        id: Hash,
+        preimage: Option<Preimage>,
+        time: &UtcDateTime,
+        block: &Block,
+    ) -> bool {
+        if time >= &block.time {
+            return false;
+        }
+
+        let Some(htlc) = self.htlcs.get_mut(&id) else {
+            return false;
+        };
+
+        if htlc.status != HtlcStatus::Open {
+            return false;
+        }
+
+        let (address, status, entry) = match preimage {
+            Some(preimage) => (
+                htlc.receiver,
+                HtlcStatus::Claimed(preimage),
+                Transaction::ClaimHtlc {
+                    amount: htlc.amount,
+                    sender: htlc.sender,
+                    htlc: id,
+                },
+            ),
+            None => (
+                htlc.sender,
+                HtlcStatus::Refunded,
+                Transaction::RefundHtlc {
+                    amount: htlc.amount,
+                    htlc: id,
+                },
+            ),
+        };
+
+        htlc.status = status;
+
+        let amount = htlc.amount;
+
+        let Some(wallet) = self.wallets.get_mut(&address) else {
+            return false;
+        };
+
+        let Some(balance) = wallet.balance.checked_add(amount) else {
+            return false;
+        };
+
+        wallet.balance = balance;
+
+        wallet.transaction_history.insert(time.clone(), entry);
+
+        true
+    }
+
+    // The amount is checked against the policy when the block is verified.
+    fn reward(&mut self, amount: Amount, receiver: &Address, block: &Block) -> bool {
+        let Some(wallet) = self.wallets.get_mut(receiver) else {
+            return false;
+        };
+
+        let Some(balance) = wallet.balance.checked_add(amount) else {
+            return false;
+        };
+
+        wallet.balance = balance;
+
+        wallet.transaction_history.insert(
+            block.time.clone(),
+            Transaction::Reward {
+                amount,
+                block: block.id,
+            },
+        );
+
+        true
+    }
+
+    // Records what the data of a block is about to change, from the block itself or from the data
+    // for a block that is still being put together.
+    fn snapshot(&self, block: &Block, data: &[BlockData]) -> Undo {
+        let miner = self.address(&block.miner);
+
+        let mut touched: HashMap<Address, HashSet<UtcDateTime>> = HashMap::new();
+        let mut ids = vec![];
+        let mut htlcs = vec![];
+
+        for data in data {
+            // The miner collects a fee at the time of most data.
+            let mut addresses = vec![miner];
+
+            let time = match data {
+                BlockData::CreateWallet { id, key, time, .. } => {
+                    addresses.push(self.address(key));
+                    ids.push(*id);
+
+                    time
+                }
+                BlockData::Transaction {
+                    time,
+                    sender,
+                    receiver,
+                    ..
+                } => {
+                    addresses.push(self.address(sender));
+                    addresses.push(*receiver);
+
+                    time
+                }
+                BlockData::BatchTransaction {
+                    time,
+                    sender,
+                    outputs,
+                    ..
+                } => {
+                    addresses.push(self.address(sender));
+                    addresses.extend(outputs.iter().map(|output| output.receiver));
+
+                    time
+                }
+                BlockData::CreateMultisigWallet {
+                    id, owners, time, ..
+                } => {
+                    addresses.push(self.multisig_address(owners));
+                    ids.push(*id);
+
+                    time
+                }
+                BlockData::MultisigTransaction {
+                    time,
+                    sender,
+                    receiver,
+                    ..
+                } => {
+                    addresses.push(self.multisig_address(sender));
+                    addresses.push(*receiver);
+
+                    time
+                }
+                BlockData::CreateHtlc { time, sender, .. } => {
+                    addresses.push(self.address(sender));
+                    htlcs.push(data.hash());
+
+                    time
+                }
+                BlockData::ClaimHtlc {
+                    htlc,
+                    time,
+                    receiver,
+                    ..
+                } => {
+                    addresses.push(self.address(receiver));
+                    htlcs.push(*htlc);
+
+                    time
+                }
+                BlockData::RefundHtlc {
+                    htlc, time, sender, ..
+                } => {
+                    addresses.push(self.address(sender));
+                    htlcs.push(*htlc);
+
+                    time
+                }
+                BlockData::Reward { receiver, .. } => {
+                    addresses.push(*receiver);
+
+                    &block.time
+                }
+            };
+
+            for address in addresses {
+                touched.entry(address).or_default().insert(time.clone());
+            }
+        }
+
+        Undo {
+            wallets: touched
+                .into_iter()
+                .map(|(address, times)| {
+                    let wallet = self.wallets.get(&address);
+
+                    WalletUndo {
+                        address,
+                        balance: wallet.map(|wallet| wallet.balance),
+                        history: times
+                            .into_iter()
+                            .map(|time| {
+                                let entry = wallet
+                                    .and_then(|wallet| wallet.transaction_history.get(&time))
+                                    .cloned();
+
+                                (time, entry)
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+            wallet_ids: ids
+                .into_iter()
+                .map(|id| (id, self.wallet_ids.get(&id).cloned()))
+                .collect(),
+            htlcs: htlcs
+                .into_iter()
+                .map(|id| (id, self.htlcs.get(&id).cloned()))
+                .collect(),
+            supply: self.supply,
+        }
+    }
+
+    fn restore(&mut self, undo: Undo) {
+        for wallet_undo in undo.wallets {
+            let Some(balance) = wallet_undo.balance else {
+                self.wallets.remove(&wallet_undo.address);
+                continue;
+            };
+
+            let Some(wallet) = self.wallets.get_mut(&wallet_undo.address) else {
+                continue;
+            };
+
+            wallet.balance = balance;
+
+            for (time, entry) in wallet_undo.history {
+                match entry {
+                    Some(entry) => wallet.transaction_history.insert(time, entry),
+                    None => wallet.transaction_history.remove(&time),
+                };
+            }
+        }
+
+        for (id, address) in undo.wallet_ids {
+            match address {
+                Some(address) => self.wallet_ids.insert(id, address),
+                None => self.wallet_ids.remove(&id),
+            };
+        }
+
+        for (id, htlc) in undo.htlcs {
+            match htlc {
+                Some(htlc) => self.htlcs.insert(id, htlc),
+                None => self.htlcs.remove(&id),
+            };
+        }
+
+        self.supply = undo.supply;
+    }
+
+    // Applies data for a block that is still being put together, so the data after it is checked
+    // against what it leaves behind. The returned undo takes it back out of the world again.
+    pub fn apply_pending(&mut self, data: &BlockData, block: &Block) -> Option<Undo> {
+        let undo = self.snapshot(block, std::slice::from_ref(data));
+
+        if !self.process_data(data, block) {
+            self.restore(undo);
+
+            return None;
+        }
+
+        Some(undo)
+    }
+
+    pub fn revert_pending(&mut self, pending: Vec<Undo>) {
+        for undo in pending.into_iter().rev() {
+            self.restore(undo);
+        }
+    }
+
+    fn connect_block(&mut self, block: Block) -> bool {
+        let mut batch = SignatureBatch::new();
+
+        batch.add_block(&block);
+
+        if let Err(item) = batch.verify() {
+            println!("Invalid signature for {}!", item);
+
+            return false;
+        }
+
+        self.connect_verified_block(block)
+    }
+
+    fn connect_verified_block(&mut self, block: Block) -> bool {
+        let undo = self.snapshot(&block, &block.data);
+
+        for data in &block.data {
+            if !self.process_data(data, &block) {
+                self.restore(undo);
+
+                return false;
+            }
+        }
+
+        let supply = block
+            .issued(self.params())
+            .and_then(|issued| issued.checked_add(self.supply));
+
+        let Some(supply) = supply else {
+            println!("Block {} issues too many coins!", block.id);
+
+            self.restore(undo);
+
+            return false;
+        };
+
+        // Summing up every wallet takes long on a big chain, so release builds trust the ledger.
+        if cfg!(debug_assertions) && !self.check_supply(supply) {
+            println!(
+                "Balances do not add up to the supply after block {}!",
+                block.id
+            );
+
+            self.restore(undo);
+
+            return false;
+        }
+
+        self.supply = supply;
+
+        self.waiting.retain(|waiting| {
+            let hash = waiting.hash();
+
+            !block.data.iter().any(|data| data.hash() == hash)
+        });
+
+        self.chain.add_block(block);
+        self.undo.push_back(undo);
+
+        // Blocks this deep are never disconnected again.
+        if self.undo.len() as u64 > self.params().max_reorg_depth {
+            self.undo.pop_front();
+        }
+
+        true
+    }
+
+    pub fn supply(&self) -> Amount {
+        self.supply
+    }
+
+    // Every coin in a wallet or an open contract was issued by some block, and none are destroyed.
+    fn check_supply(&self, supply: Amount) -> bool {
+        let balances = self.wallets.values().map(|wallet| wallet.balance);
+
+        let locked = self
+            .htlcs
+            .values()
+            .filter(|htlc| htlc.status == HtlcStatus::Open)
+            .map(|htlc| htlc.amount);
+
+        Amount::checked_sum(balances.chain(locked)) == Some(supply)
+    }
+
+    fn disconnect_block(&mut self) -> Option<Block> {
+        let undo = self.undo.pop_back()?;
+
+        self.restore(undo);
+
+        self.chain.disconnect_block()
+    }
+
+    fn reorganize(&mut self, hash: &Hash) -> bool {
+        let Some((fork, branch)) = self.chain.branch(hash) else {
+            return false;
+        };
+
+        if self.chain.blocks().len() - (fork + 1) > self.undo.len() {
+            return false;
+        }
+
+        let mut disconnected = vec![];
+
+        while self.chain.blocks().len() > fork + 1 {
+            let Some(block) = self.disconnect_block() else {
+                return false;
+            };
+
+            disconnected.push(block);
+        }
+
+        for (i, block) in branch.iter().enumerate() {
+            let Some(block) = self.chain.take_side(&block.hash) else {
+                return false;
+            };
+
+            let hash = block.hash;
+
+            if !self.connect_block(block) {
+                self.chain.discard_side(&hash);
+
+                for _ in 0..i {
+                    self.disconnect_block();
+                }
+
+                for block in disconnected.into_iter().rev() {
+                    self.chain.take_side(&block.hash);
+                    self.connect_block(block);
+                }
+
+                return false;
+            }
+        }
+
+        for block in disconnected.into_iter().rev() {
+            for data in block.data {
+                let hash = data.hash();
+
+                let included = self
+                    .chain
+                    .blocks()
+                    .iter()
+                    .skip(fork + 1)
+                    .any(|block| block.data.iter().any(|data| data.hash() == hash));
+
+                if !included && self.verify_data(&data) {
+                    self.waiting.push(data);
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn add_block(&mut self, block: Block) -> bool {
+        if self.chain.verify_next(&block) {
+            return self.connect_block(block);
+        }
+
+        if !self.chain.verify_side(&block) {
+            return false;
+        }
+
+        let hash = block.hash;
+
+        self.chain.add_side(block);
+
+        let Some(work) = self.chain.branch_work(&hash) else {
+            return true;
+        };
+
+        if work > self.chain.work() {
+            return self.reorganize(&hash);
+        }
+
+        true
+    }
+
+    pub fn new(chain: BlockChain) -> Option<Self> {
+        if !chain.verify_links() {
+            return None;
+        }
+
+        if let Err(item) = chain.verify_signatures() {
+            println!("Invalid signature for {}!", item);
+
+            return None;
+        }
+
+        let mut world = Self {
+            chain: BlockChain::new(chain.params().clone()),
+            waiting: vec![],
+            wallets: HashMap::new(),
+            wallet_ids: HashMap::new(),
+            htlcs: HashMap::new(),
+            supply: Amount::ZERO,
+            undo: VecDeque::new(),
+        };
+
+        for block in chain.blocks() {
+            if !world.connect_verified_block(block.clone()) {
+                return None;
+            }
+        }
+
+        Some(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use farcoin::{PrivateKey, Signature};
+    use rand::thread_rng;
+
+    use crate::test_util::{
+        address, block, coins, create_wallet, key, mine, now, paid_transaction, signed,
+        transaction, world,
+    };
+
+    fn wallets(world: &WorldState) -> serde_json::Value {
+        serde_json::to_value(&world.wallets).unwrap()
+    }
+
+    fn balance(world: &WorldState, key: &PrivateKey) -> Amount {
+        world.wallets[&address(key)].balance
+    }
+
+    fn height(world: &WorldState) -> u64 {
+        world.chain.tip().unwrap().id
+    }
+
+    fn create_htlc(
+        sender: &PrivateKey,
+        receiver: &PrivateKey,
+        hash_lock: Hash,
+        timeout: Lock,
+    ) -> BlockData {
+        let htlc = BlockData::CreateHtlc {
+            fee: Amount::ZERO,
+            amount: coins(20),
+            time: now(),
+            sender: PublicKey::from(sender),
+            receiver: address(receiver),
+            hash_lock,
+            timeout,
+            signature: Signature::placeholder(),
+        };
+
+        signed(htlc, sender)
+    }
+
+    fn claim_htlc(receiver: &PrivateKey, htlc: Hash, preimage: Preimage) -> BlockData {
+        let claim = BlockData::ClaimHtlc {
+            htlc,
+            preimage,
+            time: now(),
+            receiver: PublicKey::from(receiver),
+            signature: Signature::placeholder(),
+        };
+
+        signed(claim, receiver)
+    }
+
+    fn refund_htlc(sender: &PrivateKey, htlc: Hash) -> BlockData {
+        let refund = BlockData::RefundHtlc {
+            htlc,
+            time: now(),
+            sender: PublicKey::from(sender),
+            signature: Signature::placeholder(),
+        };
+
+        signed(refund, sender)
+    }
+
+    fn chain_supply(world: &WorldState) -> Amount {
+        world.chain.supply(world.chain.tip().unwrap().id).unwrap()
+    }
+
+    #[test]
+    fn disconnecting_a_block_restores_the_wallets() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![create_wallet(&student, 1)]));
+
+        let before = wallets(&world);
+        let supply = world.supply();
+        let reward = BlockData::Reward {
+            amount: world.params().reward(3),
+            receiver: address(&miner),
+        };
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![reward, transaction(&student, &miner, coins(30))]
+        ));
+        assert_ne!(wallets(&world), before);
+        assert_eq!(world.supply(), chain_supply(&world));
+
+        assert!(world.disconnect_block().is_some());
+        assert_eq!(wallets(&world), before);
+        assert_eq!(world.supply(), supply);
+        assert_eq!(world.supply(), chain_supply(&world));
+
+        assert!(world.disconnect_block().is_some());
+        assert!(!world.wallets.contains_key(&address(&student)));
+        assert!(!world.wallet_ids.contains_key(&1));
+    }
+
+    #[test]
+    fn undo_is_kept_down_to_the_deepest_reorganization() {
+        let miner = key();
+        let mut world = world(&miner);
+        let depth = world.params().max_reorg_depth;
+
+        for _ in 0..depth + 10 {
+            assert!(mine(&mut world, &miner, vec![]));
+        }
+
+        assert_eq!(world.undo.len() as u64, depth);
+    }
+
+    #[test]
+    fn longer_branches_replace_the_chain() {
+        let miner = key();
+        let student = key();
+        let mut world = world(&miner);
+
+        assert!(mine(&mut world, &miner, vec![create_wallet(&student, 1)]));
+
+        let mut other = world.clone();
+        let payment = transaction(&student, &miner, coins(30));
+
+        assert!(mine(&mut world, &miner, vec![payment.clone()]));
+
+        let first = block(&other, &miner, vec![]);
+
+        assert!(other.add_block(first.clone()));
+
+        let second = block(&other, &miner, vec![]);
+
+        assert!(other.add_block(second.clone()));
+
+        assert!(world.add_block(first));
+        assert!(world.add_block(second));
+
+        assert_eq!(
+            world.chain.tip().unwrap().hash,
+            other.chain.tip().unwrap().hash
+        );
+        assert_eq!(wallets(&world), wallets(&other));
+        assert_eq!(world.supply(), other.supply());
+        assert_eq!(world.supply(), chain_supply(&world));
+        assert_eq!(world.waiting.len(), 1);
+        assert_eq!(world.waiting[0].hash(), payment.hash());
+    }
+
+    #[test]
+    fn branches_forking_below_the_final_height_are_ignored() {
+        let miner = key();
+        let mut world = world(&miner);
+        let mut other = world.clone();
+        let depth = world.params().max_reorg_depth;
+
+        for _ in 0..depth + 2 {
+            assert!(mine(&mut world, &miner, vec![]));
+        }
+
+        let fork = block(&other, &miner, vec![]);
+
+        assert!(other.add_block(fork.clone()));
+        assert!(!world.add_block(fork));
+        assert!(world.chain.side_blocks().is_empty());
+    }
+
+    #[test]
+    fn batches_pay_the_fee_on_top_of_the_outputs() {
+        let miner = key();
+        let student = key();
+        let receivers = [key(), key()];
+        let mut world = world(&miner);
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![
+                create_wallet(&student, 1),
+                create_wallet(&receivers[0], 2),
+                create_wallet(&receivers[1], 3),
+            ]
+        ));
+
+        // A single transaction could not pay a fee larger than its amount.
+        let fee = coins(5);
+
+        assert!(!world.params().check_fee(fee, coins(2)));
+
+        let batch = BlockData::BatchTransaction {
+            fee,
+            time: now(),
+            sender: PublicKey::from(&student),
+            outputs: receivers
+                .iter()
+                .map(|receiver| Output {
+                    receiver: address(receiver),
+                    amount: coins(1),
+                })
+                .collect(),
+            memo: None,
+            expiry: None,
+            signature: Signature::placeholder(),
+        };
+
+        let sent = balance(&world, &student);
+        let collected = balance(&world, &miner);
+        let received = balance(&world, &receivers[0]);
+
+        assert!(mine(&mut world, &miner, vec![signed(batch, &student)]));
+
+        assert_eq!(
+            balance(&world, &student),
+            sent.checked_sub(coins(7)).unwrap()
+        );
+        assert_eq!(balance(&world, &miner), collected.checked_add(fee).unwrap());
+
+        for receiver in &receivers {
+            assert_eq!(
+                balance(&world, receiver),
+                received.checked_add(coins(1)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn htlcs_are_claimed_with_the_preimage() {
+        let miner = key();
+        let sender = key();
+        let receiver = key();
+        let mut world = world(&miner);
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![create_wallet(&sender, 1), create_wallet(&receiver, 2)]
+        ));
+
+        let preimage = Preimage::random(thread_rng());
+        let timeout = Lock::Height(height(&world) + 10);
+        let contract = create_htlc(&sender, &receiver, preimage.hash_lock(), timeout);
+        let id = contract.hash();
+        let sent = balance(&world, &sender);
+        let received = balance(&world, &receiver);
+
+        assert!(mine(&mut world, &miner, vec![contract]));
+        assert_eq!(
+            balance(&world, &sender),
+            sent.checked_sub(coins(20)).unwrap()
+        );
+        assert!(world.check_supply(world.supply()));
+
+        let wrong_preimage = claim_htlc(&receiver, id, Preimage::random(thread_rng()));
+        let wrong_receiver = claim_htlc(&sender, id, preimage);
+
+        assert!(!world.verify_data(&wrong_preimage));
+        assert!(!mine(&mut world, &miner, vec![wrong_preimage]));
+        assert!(!mine(&mut world, &miner, vec![wrong_receiver]));
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![claim_htlc(&receiver, id, preimage)]
+        ));
+        assert_eq!(
+            balance(&world, &receiver),
+            received.checked_add(coins(20)).unwrap()
+        );
+        assert!(world.htlcs[&id].status == HtlcStatus::Claimed(preimage));
+        assert!(world.check_supply(world.supply()));
+
+        assert!(!mine(&mut world, &miner, vec![refund_htlc(&sender, id)]));
+    }
+
+    #[test]
+    fn htlcs_are_refunded_after_the_timeout() {
+        let miner = key();
+        let sender = key();
+        let receiver = key();
+        let mut world = world(&miner);
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![create_wallet(&sender, 1), create_wallet(&receiver, 2)]
+        ));
+
+        let preimage = Preimage::random(thread_rng());
+        let timeout = Lock::Height(height(&world) + 3);
+        let contract = create_htlc(&sender, &receiver, preimage.hash_lock(), timeout);
+        let id = contract.hash();
+        let sent = balance(&world, &sender);
+
+        assert!(mine(&mut world, &miner, vec![contract]));
+
+        let refund = refund_htlc(&sender, id);
+
+        assert!(!world.is_unlocked(&refund, height(&world) + 1, &now()));
+        assert!(!mine(&mut world, &miner, vec![refund]));
+
+        assert!(mine(&mut world, &miner, vec![]));
+
+        // Claims are no longer accepted once the contract can be refunded.
+        let claim = claim_htlc(&receiver, id, preimage);
+
+        assert!(world.is_expired(&claim, height(&world) + 1, &now()));
+        assert!(!mine(&mut world, &miner, vec![claim]));
+
+        assert!(mine(&mut world, &miner, vec![refund_htlc(&sender, id)]));
+        assert_eq!(balance(&world, &sender), sent);
+        assert!(world.htlcs[&id].status == HtlcStatus::Refunded);
+        assert!(world.check_supply(world.supply()));
+    }
+
+    #[test]
+    fn htlcs_past_their_timeout_are_rejected() {
+        let miner = key();
+        let sender = key();
+        let receiver = key();
+        let mut world = world(&miner);
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![create_wallet(&sender, 1), create_wallet(&receiver, 2)]
+        ));
+
+        let hash_lock = Preimage::random(thread_rng()).hash_lock();
+        let timeout = Lock::Height(height(&world) + 1);
+        let contract = create_htlc(&sender, &receiver, hash_lock, timeout);
+
+        assert!(!crate::submit::queue(&mut world, contract.clone()).valid);
+        assert!(!mine(&mut world, &miner, vec![contract]));
+    }
+
+    // Alice and Bob swap coins between two separate chains, each kept by its own node.
+    #[test]
+    fn htlcs_swap_coins_between_two_nodes() {
+        let (alice, bob) = (key(), key());
+        let (first_miner, second_miner) = (key(), key());
+        let mut first = world(&first_miner);
+        let mut second = world(&second_miner);
+
+        let wallets = vec![create_wallet(&alice, 1), create_wallet(&bob, 2)];
+
+        assert!(mine(&mut first, &first_miner, wallets.clone()));
+        assert!(mine(&mut second, &second_miner, wallets));
+
+        // Only Alice knows the preimage. Bob's contract times out well before hers.
+        let preimage = Preimage::random(thread_rng());
+
+        let alice_contract = create_htlc(
+            &alice,
+            &bob,
+            preimage.hash_lock(),
+            Lock::Height(height(&first) + 20),
+        );
+        let bob_contract = create_htlc(
+            &bob,
+            &alice,
+            preimage.hash_lock(),
+            Lock::Height(height(&second) + 10),
+        );
+        let (alice_id, bob_id) = (alice_contract.hash(), bob_contract.hash());
+
+        assert!(mine(&mut first, &first_miner, vec![alice_contract]));
+        assert!(mine(&mut second, &second_miner, vec![bob_contract]));
+
+        let alice_balance = balance(&second, &alice);
+        let bob_balance = balance(&first, &bob);
+
+        assert!(mine(
+            &mut second,
+            &second_miner,
+            vec![claim_htlc(&alice, bob_id, preimage)]
+        ));
+
+        // Bob learns the preimage from Alice's claim on his chain.
+        let HtlcStatus::Claimed(revealed) = second.htlcs[&bob_id].status else {
+            panic!("Bob's contract is claimed");
+        };
+
+        assert!(mine(
+            &mut first,
+            &first_miner,
+            vec![claim_htlc(&bob, alice_id, revealed)]
+        ));
+
+        assert_eq!(
+            balance(&second, &alice),
+            alice_balance.checked_add(coins(20)).unwrap()
+        );
+        assert_eq!(
+            balance(&first, &bob),
+            bob_balance.checked_add(coins(20)).unwrap()
+        );
+        assert!(first.check_supply(first.supply()));
+        assert!(second.check_supply(second.supply()));
+    }
+
+    #[test]
+    fn fees_go_unpaid_without_a_miner_wallet() {
+        let miner = key();
+        let stranger = key();
+        let (sender, receiver) = (key(), key());
+        let mut world = world(&miner);
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![create_wallet(&sender, 1), create_wallet(&receiver, 2)]
+        ));
+
+        let sent = balance(&world, &sender);
+        let received = balance(&world, &receiver);
+        let payment = paid_transaction(&sender, &receiver, coins(10), coins(1));
+
+        assert!(world.add_block(block(&world, &stranger, vec![payment])));
+
+        assert_eq!(
+            balance(&world, &sender),
+            sent.checked_sub(coins(9)).unwrap()
+        );
+        assert_eq!(
+            balance(&world, &receiver),
+            received.checked_add(coins(9)).unwrap()
+        );
+        assert!(!world.wallets.contains_key(&address(&stranger)));
+        assert!(world.check_supply(world.supply()));
+    }
+
+    #[test]
+    fn multisig_wallets_spend_with_enough_owners() {
+        let miner = key();
+        let owners = [key(), key(), key()];
+        let receiver = key();
+        let mut world = world(&miner);
+
+        let multisig = Multisig::new(2, owners.iter().map(PublicKey::from).collect()).unwrap();
+        let wallet = world.multisig_address(&multisig);
+
+        let mut create = BlockData::CreateMultisigWallet {
+            id: 1,
+            owners: multisig.clone(),
+            time: now(),
+            signatures: vec![],
+        };
+
+        assert!(create.sign(&owners[0]));
+        assert!(!world.verify_data(&create));
+        assert!(create.sign(&owners[2]));
+
+        assert!(mine(
+            &mut world,
+            &miner,
+            vec![create, create_wallet(&receiver, 2)]
+        ));
+
+        // Multisig wallets start out empty.
+        let funding = BlockData::Transaction {
+            fee: Amount::ZERO,
+            amount: coins(50),
+            time: now(),
+            sender: PublicKey::from(&receiver),
+            receiver: wallet,
+            memo: None,
+            lock: None,
+            expiry: None,
+            signature: Signature::placeholder(),
+        };
+
+        assert!(mine(&mut world, &miner, vec![signed(funding, &receiver)]));
+
+        let sent = world.wallets[&wallet].balance;
+        let received = balance(&world, &receiver);
+
+        let mut payment = BlockData::MultisigTransaction {
+            fee: Amount::ZERO,
+            amount: coins(30),
+            time: now(),
+            sender: multisig,
+            receiver: address(&receiver),
+            memo: None,
+            expiry: None,
+            signatures: vec![],
+        };
+
+        assert!(payment.sign(&owners[1]));
+        assert!(!world.verify_data(&payment));
+        assert!(!mine(&mut world, &miner, vec![payment.clone()]));
+
+        // Signing twice fills the same slot.
+        assert!(payment.sign(&owners[1]));
+        assert!(!world.verify_data(&payment));
+
+        assert!(!payment.sign(&receiver));
+        assert!(payment.sign(&owners[2]));
+        assert!(mine(&mut world, &miner, vec![payment]));
+
+        assert_eq!(
+            world.wallets[&wallet].balance,
+            sent.checked_sub(coins(30)).unwrap()
+        );
+        assert_eq!(
+            balance(&world, &receiver),
+            received.checked_add(coins(30)).unwrap()
+        );
+    }
+
+    #[test]
+    fn multisig_wallets_need_valid_policies() {
+        let miner = key();
+        let owner = key();
+        let world = world(&miner);
+
+        let keys = vec![PublicKey::from(&owner), PublicKey::from(&owner)];
+
+        for threshold in [0, 2, 3] {
+            let json = serde_json::json!({ "threshold": threshold, "keys": keys });
+            let owners: Multisig = serde_json::from_value(json).unwrap();
+
+            let mut create = BlockData::CreateMultisigWallet {
+                id: 1,
+                owners,
+                time: now(),
+                signatures: vec![],
+            };
+
+            assert!(create.sign(&owner));
+            assert!(!world.verify_data(&create));
+        }
     }
 }
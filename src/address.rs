@@ -0,0 +1,256 @@
+use std::fmt;
+
+use bech32::{FromBase32, ToBase32};
+use serde::{de, Deserialize, Serialize};
+
+use crate::{util::SerdeVisitor, Multisig, PublicKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address {
+    network: Network,
+    version: u8,
+    hash: [u8; 20],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressError {
+    Bech32(bech32::Error),
+    Variant,
+    Prefix(String),
+    MissingVersion,
+    Length(usize),
+}
+
+impl Network {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Regtest => "regtest",
+        }
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Mainnet => "far",
+            Self::Testnet => "tfar",
+            Self::Regtest => "rfar",
+        }
+    }
+
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        [Self::Mainnet, Self::Testnet, Self::Regtest]
+            .into_iter()
+            .find(|network| network.prefix() == prefix)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Network {
+    type Error = NetworkError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        [Self::Mainnet, Self::Testnet, Self::Regtest]
+            .into_iter()
+            .find(|network| network.name() == value)
+            .ok_or_else(|| NetworkError::Unknown(value.into()))
+    }
+}
+
+impl Address {
+    pub const KEY_HASH: u8 = 0;
+    pub const MULTISIG_HASH: u8 = 1;
+
+    pub fn new(network: Network, key: &PublicKey) -> Self {
+        Self::from_data(network, Self::KEY_HASH, &key.to_bytes())
+    }
+
+    pub fn multisig(network: Network, multisig: &Multisig) -> Self {
+        Self::from_data(network, Self::MULTISIG_HASH, &multisig.to_bytes())
+    }
+
+    fn from_data(network: Network, version: u8, data: &[u8]) -> Self {
+        use sha3::{Digest, Sha3_256};
+
+        let digest = Sha3_256::digest(data);
+
+        let mut hash = [0; 20];
+
+        hash.copy_from_slice(&digest[..20]);
+
+        Self {
+            network,
+            version,
+            hash,
+        }
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn hash(&self) -> &[u8; 20] {
+        &self.hash
+    }
+}
+
+impl From<&Address> for String {
+    fn from(address: &Address) -> Self {
+        let version =
+            bech32::u5::try_from_u8(address.version).expect("address versions fit in five bits");
+
+        let mut data = vec![version];
+
+        data.extend(address.hash.to_base32());
+
+        bech32::encode(address.network.prefix(), data, bech32::Variant::Bech32m)
+            .expect("address prefixes are valid")
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Address {
+    type Error = AddressError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let (prefix, data, variant) = bech32::decode(value).map_err(AddressError::Bech32)?;
+
+        if variant != bech32::Variant::Bech32m {
+            return Err(AddressError::Variant);
+        }
+
+        let Some(network) = Network::from_prefix(&prefix) else {
+            return Err(AddressError::Prefix(prefix));
+        };
+
+        let Some((version, data)) = data.split_first() else {
+            return Err(AddressError::MissingVersion);
+        };
+
+        let bytes = Vec::<u8>::from_base32(data).map_err(AddressError::Bech32)?;
+
+        let hash =
+            <[u8; 20]>::try_from(bytes).map_err(|bytes| AddressError::Length(bytes.len()))?;
+
+        Ok(Self {
+            network,
+            version: version.to_u8(),
+            hash,
+        })
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown network {}", name),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bech32(error) => write!(f, "invalid bech32 ({})", error),
+            Self::Variant => f.write_str("expected bech32m, got bech32"),
+            Self::Prefix(prefix) => write!(f, "unknown prefix {}", prefix),
+            Self::MissingVersion => f.write_str("missing address version"),
+            Self::Length(length) => write!(f, "expected a 20 byte hash, got {}", length),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+impl Serialize for Address {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&String::from(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = deserializer.deserialize_str(SerdeVisitor)?;
+
+        Self::try_from(s.as_str())
+            .map_err(|error| de::Error::custom(format!("invalid address: {}", error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PrivateKey;
+
+    #[test]
+    fn addresses_round_trip() {
+        let key = PublicKey::from(&PrivateKey::random(rand_core::OsRng));
+        let address = Address::new(Network::Regtest, &key);
+
+        assert_eq!(
+            Address::try_from(String::from(&address).as_str()),
+            Ok(address)
+        );
+    }
+
+    #[test]
+    fn malformed_addresses_are_rejected() {
+        let encode = |prefix, data: &[u8], variant| {
+            let mut data = data.to_base32();
+
+            data.insert(0, bech32::u5::try_from_u8(0).unwrap());
+
+            bech32::encode(prefix, data, variant).unwrap()
+        };
+
+        assert!(matches!(
+            Address::try_from("rfar1invalid"),
+            Err(AddressError::Bech32(_))
+        ));
+        assert_eq!(
+            Address::try_from(encode("rfar", &[0; 20], bech32::Variant::Bech32).as_str()),
+            Err(AddressError::Variant)
+        );
+        assert_eq!(
+            Address::try_from(encode("btc", &[0; 20], bech32::Variant::Bech32m).as_str()),
+            Err(AddressError::Prefix("btc".into()))
+        );
+        assert_eq!(
+            Address::try_from(encode("rfar", &[0; 19], bech32::Variant::Bech32m).as_str()),
+            Err(AddressError::Length(19))
+        );
+
+        let btc = serde_json::Value::from(encode("btc", &[0; 20], bech32::Variant::Bech32m));
+        let error = serde_json::from_value::<Address>(btc).unwrap_err();
+
+        assert_eq!(error.to_string(), "invalid address: unknown prefix btc");
+
+        assert_eq!(
+            Network::try_from("devnet"),
+            Err(NetworkError::Unknown("devnet".into()))
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Serialize};
+
+use crate::util::SerdeVisitor;
+
+// A number of coins, kept as a whole number of base units. Written as a decimal number of coins,
+// such as "12.5".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    Invalid,
+    TooPrecise,
+    Overflow,
+}
+
+impl Amount {
+    pub const DECIMALS: u32 = 8;
+    pub const UNITS_PER_COIN: u64 = 10u64.pow(Self::DECIMALS);
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_units(units: u64) -> Self {
+        Self(units)
+    }
+
+    pub fn from_coins(coins: u64) -> Option<Self> {
+        coins.checked_mul(Self::UNITS_PER_COIN).map(Self)
+    }
+
+    pub fn units(&self) -> u64 {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    pub fn saturating_mul(self, factor: u64) -> Self {
+        Self(self.0.saturating_mul(factor))
+    }
+
+    pub fn checked_sum(amounts: impl IntoIterator<Item = Self>) -> Option<Self> {
+        amounts
+            .into_iter()
+            .try_fold(Self::ZERO, |total, amount| total.checked_add(amount))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = AmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (coins, fraction) = s.split_once('.').unwrap_or((s, ""));
+
+        let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+
+        if coins.is_empty() || !is_digits(coins) || !is_digits(fraction) {
+            return Err(AmountError::Invalid);
+        }
+
+        if s.ends_with('.') {
+            return Err(AmountError::Invalid);
+        }
+
+        if fraction.len() > Self::DECIMALS as usize {
+            return Err(AmountError::TooPrecise);
+        }
+
+        let Ok(coins) = coins.parse::<u64>() else {
+            return Err(AmountError::Overflow);
+        };
+
+        let fraction = format!("{:0<width$}", fraction, width = Self::DECIMALS as usize);
+
+        let Ok(units) = fraction.parse::<u64>() else {
+            return Err(AmountError::Invalid);
+        };
+
+        Self::from_coins(coins)
+            .and_then(|amount| amount.checked_add(Self(units)))
+            .ok_or(AmountError::Overflow)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coins = self.0 / Self::UNITS_PER_COIN;
+        let units = self.0 % Self::UNITS_PER_COIN;
+
+        if units == 0 {
+            return write!(f, "{}", coins);
+        }
+
+        let fraction = format!("{:0width$}", units, width = Self::DECIMALS as usize);
+
+        write!(f, "{}.{}", coins, fraction.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid => f.write_str("not a decimal number of coins"),
+            Self::TooPrecise => write!(f, "more than {} decimal places", Amount::DECIMALS),
+            Self::Overflow => f.write_str("too large"),
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = deserializer.deserialize_str(SerdeVisitor)?;
+
+        Self::from_str(&s).map_err(|error| de::Error::custom(format!("invalid amount: {}", error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_parse_as_decimal_coins() {
+        let parse = |s: &str| s.parse::<Amount>();
+
+        assert_eq!(parse("12.5"), Ok(Amount::from_units(1_250_000_000)));
+        assert_eq!(parse("0.00000001"), Ok(Amount::from_units(1)));
+        assert_eq!(
+            parse("7"),
+            Amount::from_coins(7).ok_or(AmountError::Overflow)
+        );
+        assert_eq!(
+            parse("184467440737.09551615"),
+            Ok(Amount::from_units(u64::MAX))
+        );
+
+        assert_eq!(parse("0.000000001"), Err(AmountError::TooPrecise));
+        assert_eq!(parse("184467440737.09551616"), Err(AmountError::Overflow));
+        assert_eq!(parse("184467440738"), Err(AmountError::Overflow));
+        assert_eq!(parse("99999999999999999999"), Err(AmountError::Overflow));
+
+        for invalid in ["", "1.", ".5", "-1", "+1", "1.2.3", "1e5", " 1", "1,5"] {
+            assert_eq!(parse(invalid), Err(AmountError::Invalid), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn amounts_display_without_trailing_zeros() {
+        assert_eq!(Amount::ZERO.to_string(), "0");
+        assert_eq!(Amount::from_units(1_250_000_000).to_string(), "12.5");
+        assert_eq!(Amount::from_units(100_000_001).to_string(), "1.00000001");
+        assert_eq!(Amount::from_units(1_000_000).to_string(), "0.01");
+        assert_eq!(Amount::from_coins(21).unwrap().to_string(), "21");
+    }
+
+    #[test]
+    fn amounts_serialize_as_strings() {
+        let amount = Amount::from_units(1_234_500_000);
+        let json = serde_json::to_string(&amount).unwrap();
+
+        assert_eq!(json, "\"12.345\"");
+        assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), amount);
+        assert!(serde_json::from_str::<Amount>("\"0.000000001\"").is_err());
+        assert!(serde_json::from_str::<Amount>("12").is_err());
+    }
+
+    #[test]
+    fn arithmetic_checks_for_overflow() {
+        let max = Amount::from_units(u64::MAX);
+        let one = Amount::from_units(1);
+
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(Amount::ZERO.checked_sub(one), None);
+        assert_eq!(one.checked_add(one), Some(Amount::from_units(2)));
+        assert_eq!(max.checked_sub(max), Some(Amount::ZERO));
+        assert_eq!(Amount::checked_sum([max, one]), None);
+        assert_eq!(
+            Amount::checked_sum([one, one, one]),
+            Some(Amount::from_units(3))
+        );
+        assert_eq!(Amount::from_coins(u64::MAX), None);
+        assert_eq!(max.saturating_add(one), max);
+        assert_eq!(Amount::ZERO.saturating_sub(one), Amount::ZERO);
+    }
+}
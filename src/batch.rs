@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Block, BlockData, Hash, PublicKey, Signature};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignedItem {
+    Block { id: u64 },
+    Data { block: u64, index: usize },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignatureBatch<'a> {
+    items: Vec<(SignedItem, &'a PublicKey, Hash, &'a Signature)>,
+}
+
+impl std::fmt::Display for SignedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Block { id } => write!(f, "block {}", id),
+            Self::Data { block, index } => write!(f, "item {} of block {}", index, block),
+        }
+    }
+}
+
+impl<'a> SignatureBatch<'a> {
+    // Below this many signatures per thread, spawning threads costs more than it saves.
+    const MIN_CHUNK: usize = 64;
+
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(
+        &mut self,
+        item: SignedItem,
+        key: &'a PublicKey,
+        hash: Hash,
+        signature: &'a Signature,
+    ) {
+        self.items.push((item, key, hash, signature));
+    }
+
+    // Only the signatures that are present are added; whether a multisig threshold is met is up
+    // to the caller.
+    pub fn add_block(&mut self, block: &'a Block) {
+        self.push(
+            SignedItem::Block { id: block.id },
+            &block.miner,
+            block.hash(),
+            &block.signature,
+        );
+
+        for (index, data) in block.data.iter().enumerate() {
+            let item = SignedItem::Data {
+                block: block.id,
+                index,
+            };
+
+            match data {
+                BlockData::CreateWallet { key, signature, .. } => {
+                    self.push(item, key, data.hash(), signature);
+                }
+                BlockData::Transaction {
+                    sender, signature, ..
+                }
+                | BlockData::BatchTransaction {
+                    sender, signature, ..
+                }
+                | BlockData::CreateHtlc {
+                    sender, signature, ..
+                }
+                | BlockData::ClaimHtlc {
+                    receiver: sender,
+                    signature,
+                    ..
+                }
+                | BlockData::RefundHtlc {
+                    sender, signature, ..
+                } => {
+                    self.push(item, sender, data.hash(), signature);
+                }
+                BlockData::CreateMultisigWallet {
+                    owners: multisig,
+                    signatures,
+                    ..
+                }
+                | BlockData::MultisigTransaction {
+                    sender: multisig,
+                    signatures,
+                    ..
+                } => {
+                    let hash = data.hash();
+
+                    for (key, signature) in multisig.signatures(signatures) {
+                        self.push(item, key, hash, signature);
+                    }
+                }
+                // Covered by the miner's signature.
+                BlockData::Reward { .. } => {}
+            }
+        }
+    }
+
+    // Returns the first item (in the order they were added) whose signature is invalid.
+    pub fn verify(&self) -> Result<(), SignedItem> {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let size = self.items.len().div_ceil(threads).max(Self::MIN_CHUNK);
+
+        if self.items.len() <= size {
+            return verify_chunk(&self.items);
+        }
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .items
+                .chunks(size)
+                .map(|chunk| scope.spawn(move || verify_chunk(chunk)))
+                .collect();
+
+            handles.into_iter().try_for_each(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+        })
+    }
+}
+
+fn verify_chunk(chunk: &[(SignedItem, &PublicKey, Hash, &Signature)]) -> Result<(), SignedItem> {
+    let batch: Vec<_> = chunk
+        .iter()
+        .map(|(_, key, hash, signature)| (*key, hash, *signature))
+        .collect();
+
+    if PublicKey::verify_batch(rand_core::OsRng, &batch) {
+        return Ok(());
+    }
+
+    // The batch only says that something is wrong, so look for the culprit one at a time.
+    match chunk
+        .iter()
+        .find(|(_, key, hash, signature)| !key.verify(hash, signature))
+    {
+        Some((item, ..)) => Err(*item),
+        None => Ok(()),
+    }
+}
@@ -1,31 +1,91 @@
-use hex::{FromHex, ToHex};
+use std::{fmt, str::FromStr};
+
+use hex::{FromHex, FromHexError, ToHex};
 use serde::{de, Deserialize, Serialize};
 
 use crate::util::SerdeVisitor;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Hash(Vec<u8>);
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash)]
+pub struct Hash([u8; 32]);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HashError {
+    Hex(FromHexError),
+    Length(usize),
+}
 
 impl Hash {
-    pub fn new(bytes: Vec<u8>) -> Self {
+    pub const LEN: usize = 32;
+
+    pub fn new(bytes: [u8; 32]) -> Self {
         Self(bytes)
     }
 
-    pub fn empty() -> Self {
-        Self(vec![])
+    // The previous hash of the genesis block, and the hash of blocks that are yet to be mined.
+    pub fn zero() -> Self {
+        Self([0; 32])
     }
 
-    pub fn bytes(&self) -> &[u8] {
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 32]
+    }
+
+    pub fn bytes(&self) -> &[u8; 32] {
         &self.0
     }
 }
 
+impl Default for Hash {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl FromStr for Hash {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = Vec::<u8>::from_hex(s).map_err(HashError::Hex)?;
+
+        let length = bytes.len();
+
+        let Ok(bytes) = <[u8; 32]>::try_from(bytes) else {
+            return Err(HashError::Length(length));
+        };
+
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.encode_hex::<String>())
+    }
+}
+
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hash({})", self)
+    }
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex(error) => write!(f, "invalid hex ({})", error),
+            Self::Length(length) => write!(f, "expected 32 bytes, got {}", length),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
 impl Serialize for Hash {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.0.encode_hex::<String>())
+        serializer.serialize_str(&self.to_string())
     }
 }
 
@@ -36,8 +96,24 @@ impl<'de> Deserialize<'de> for Hash {
     {
         let s = deserializer.deserialize_str(SerdeVisitor)?;
 
-        let bytes = Vec::<u8>::from_hex(&s).map_err(de::Error::custom)?;
+        Self::from_str(&s).map_err(|error| de::Error::custom(format!("invalid hash: {}", error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(Self(bytes))
+    #[test]
+    fn hashes_must_be_32_bytes_of_hex() {
+        let hash = Hash::new([0xab; 32]);
+
+        assert_eq!(Hash::from_str(&hash.to_string()), Ok(hash));
+        assert_eq!(Hash::from_str(&"ab".repeat(31)), Err(HashError::Length(31)));
+        assert_eq!(Hash::from_str(&"ab".repeat(33)), Err(HashError::Length(33)));
+        assert!(matches!(
+            Hash::from_str(&"zz".repeat(32)),
+            Err(HashError::Hex(_))
+        ));
     }
 }
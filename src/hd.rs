@@ -0,0 +1,443 @@
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::PrimeField;
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use crate::{PrivateKey, PublicKey};
+
+pub struct Mnemonic(bip39::Mnemonic);
+
+pub struct ExtendedPrivateKey {
+    key: PrivateKey,
+    chain_code: Zeroizing<[u8; 32]>,
+    depth: u8,
+    index: u32,
+}
+
+impl Mnemonic {
+    pub fn generate(
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        word_count: usize,
+    ) -> Option<Self> {
+        if !matches!(word_count, 12 | 15 | 18 | 21 | 24) {
+            return None;
+        }
+
+        let mut entropy = Zeroizing::new(vec![0; word_count / 3 * 4]);
+
+        rng.fill_bytes(&mut entropy);
+
+        let Ok(mnemonic) = bip39::Mnemonic::from_entropy(&entropy) else {
+            return None;
+        };
+
+        Some(Self(mnemonic))
+    }
+
+    pub fn parse(phrase: &str) -> Option<Self> {
+        let Ok(mnemonic) = bip39::Mnemonic::parse(phrase) else {
+            return None;
+        };
+
+        Some(Self(mnemonic))
+    }
+
+    pub fn phrase(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.0.to_string())
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.0.word_count()
+    }
+
+    pub fn to_seed(&self, passphrase: &str) -> Zeroizing<[u8; 64]> {
+        Zeroizing::new(self.0.to_seed(passphrase))
+    }
+}
+
+impl ExtendedPrivateKey {
+    pub const HARDENED: u32 = 1 << 31;
+    pub const WALLET_PATH: &str = "m/0'";
+
+    pub fn from_seed(seed: &[u8]) -> Option<Self> {
+        let (key, chain_code) = split(hmac_sha512(b"Bitcoin seed", &[seed]));
+
+        Some(Self {
+            key: PrivateKey::from_bytes(key.as_ref()).ok()?,
+            chain_code,
+            depth: 0,
+            index: 0,
+        })
+    }
+
+    pub fn from_mnemonic(mnemonic: &Mnemonic, passphrase: &str) -> Option<Self> {
+        Self::from_seed(mnemonic.to_seed(passphrase).as_ref())
+    }
+
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.key
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from(&self.key)
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn derive_child(&self, index: u32) -> Option<Self> {
+        let key = self.key.to_bytes();
+        let index_bytes = index.to_be_bytes();
+
+        let data = if index >= Self::HARDENED {
+            hmac_sha512(
+                self.chain_code.as_ref(),
+                &[&[0], key.as_ref(), &index_bytes],
+            )
+        } else {
+            hmac_sha512(
+                self.chain_code.as_ref(),
+                &[&self.public_key().to_bytes(), &index_bytes],
+            )
+        };
+
+        let (tweak, chain_code) = split(data);
+
+        let tweak = Option::<k256::Scalar>::from(k256::Scalar::from_repr((*tweak).into()))?;
+        let parent = Option::<k256::Scalar>::from(k256::Scalar::from_repr((*key).into()))?;
+
+        let child: Zeroizing<[u8; 32]> = Zeroizing::new((tweak + parent).to_bytes().into());
+
+        Some(Self {
+            key: PrivateKey::from_bytes(child.as_ref()).ok()?,
+            chain_code,
+            depth: self.depth.checked_add(1)?,
+            index,
+        })
+    }
+
+    pub fn derive_path(&self, path: &str) -> Option<Self> {
+        let mut components = path.trim().split('/');
+
+        if components.next()? != "m" {
+            return None;
+        }
+
+        let mut key = self.clone();
+
+        for component in components {
+            let index = match component.strip_suffix(['\'', 'h']) {
+                Some(index) => {
+                    index
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|index| *index < Self::HARDENED)?
+                        | Self::HARDENED
+                }
+                None => component
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|index| *index < Self::HARDENED)?,
+            };
+
+            key = key.derive_child(index)?;
+        }
+
+        Some(key)
+    }
+
+    pub fn derive_wallet(&self, index: u32) -> Option<Self> {
+        if index >= Self::HARDENED {
+            return None;
+        }
+
+        self.derive_path(Self::WALLET_PATH)?
+            .derive_child(index | Self::HARDENED)
+    }
+}
+
+impl Clone for ExtendedPrivateKey {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            chain_code: self.chain_code.clone(),
+            depth: self.depth,
+            index: self.index,
+        }
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+
+    for data in data {
+        mac.update(data);
+    }
+
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+fn split(bytes: Zeroizing<[u8; 64]>) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let mut left = Zeroizing::new([0; 32]);
+    let mut right = Zeroizing::new([0; 32]);
+
+    left.copy_from_slice(&bytes[..32]);
+    right.copy_from_slice(&bytes[32..]);
+
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hands out the given bytes as the entropy of a new mnemonic.
+    struct FixedRng(Vec<u8>);
+
+    impl rand_core::RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.copy_from_slice(&self.0.drain(..dest.len()).collect::<Vec<_>>());
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+
+            Ok(())
+        }
+    }
+
+    impl rand_core::CryptoRng for FixedRng {}
+
+    // The English vectors of BIP39 as (entropy, mnemonic, seed with the passphrase "TREZOR").
+    const BIP39_VECTORS: [(&str, &str, &str); 24] = [
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+        (
+            "80808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+        ),
+        (
+            "000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+            "035895f2f481b1b0f01fcf8c289c794660b289981a78f8106447707fdd9666ca06da5a9a565181599b79f53b844d8a71dd9f439c52a3d7b3e8a79c906ac845fa",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal will",
+            "f2b94508732bcbacbcc020faefecfc89feafa6649a5491b8c952cede496c214a0c7b3c392d168748f2d4a612bada0753b52a1c7ac53c1e93abd5c6320b9e95dd",
+        ),
+        (
+            "808080808080808080808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter always",
+            "107d7c02a5aa6f38c58083ff74f04c607c2d2c0ecc55501dadd72d025b751bc27fe913ffb796f841c49b1d33b610cf0e91d3aa239027f5e99fe4ce9e5088cd65",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo when",
+            "0cd6e5d827bb62eb8fc1e262254223817fd068a74b5b449cc2f667c3f1f985a76379b43348d952e2265b4cd129090758b3e3c2c49103b5051aac2eaeb890a528",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+            "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth title",
+            "bc09fca1804f7e69da93c2f2028eb238c227f2e9dda30cd63699232578480a4021b146ad717fbb7e451ce9eb835f43620bf5c514db0f8add49f5d121449d3e87",
+        ),
+        (
+            "8080808080808080808080808080808080808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+            "c0c519bd0e91a2ed54357d9d1ebef6f5af218a153624cf4f2da911a0ed8f7a09e2ef61af0aca007096df430022f7a2b6fb91661a9589097069720d015e4e982f",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+            "dd48c104698c30cfe2b6142103248622fb7bb0ff692eebb00089b32d22484e1613912f0a5b694407be899ffd31ed3992c456cdf60f5d4564b8ba3f05a69890ad",
+        ),
+        (
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+            "274ddc525802f7c828d8ef7ddbcdc5304e87ac3535913611fbbfa986d0c9e5476c91689f9c8a54fd55bd38606aa6a8595ad213d4c9c9f9aca3fb217069a41028",
+        ),
+        (
+            "6610b25967cdcca9d59875f5cb50b0ea75433311869e930b",
+            "gravity machine north sort system female filter attitude volume fold club stay feature office ecology stable narrow fog",
+            "628c3827a8823298ee685db84f55caa34b5cc195a778e52d45f59bcf75aba68e4d7590e101dc414bc1bbd5737666fbbef35d1f1903953b66624f910feef245ac",
+        ),
+        (
+            "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c",
+            "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy gospel tennis maple dilemma loan word shrug inflict delay length",
+            "64c87cde7e12ecf6704ab95bb1408bef047c22db4cc7491c4271d170a1b213d20b385bc1588d9c7b38f1b39d415665b8a9030c9ec653d75e65f847d8fc1fc440",
+        ),
+        (
+            "c0ba5a8e914111210f2bd131f3d5e08d",
+            "scheme spot photo card baby mountain device kick cradle pact join borrow",
+            "ea725895aaae8d4c1cf682c1bfd2d358d52ed9f0f0591131b559e2724bb234fca05aa9c02c57407e04ee9dc3b454aa63fbff483a8b11de949624b9f1831a9612",
+        ),
+        (
+            "6d9be1ee6ebd27a258115aad99b7317b9c8d28b6d76431c3",
+            "horn tenant knee talent sponsor spell gate clip pulse soap slush warm silver nephew swap uncle crack brave",
+            "fd579828af3da1d32544ce4db5c73d53fc8acc4ddb1e3b251a31179cdb71e853c56d2fcb11aed39898ce6c34b10b5382772db8796e52837b54468aeb312cfc3d",
+        ),
+        (
+            "9f6a2878b2520799a44ef18bc7df394e7061a224d2c33cd015b157d746869863",
+            "panda eyebrow bullet gorilla call smoke muffin taste mesh discover soft ostrich alcohol speed nation flash devote level hobby quick inner drive ghost inside",
+            "72be8e052fc4919d2adf28d5306b5474b0069df35b02303de8c1729c9538dbb6fc2d731d5f832193cd9fb6aeecbc469594a70e3dd50811b5067f3b88b28c3e8d",
+        ),
+        (
+            "23db8160a31d3e0dca3688ed941adbf3",
+            "cat swing flag economy stadium alone churn speed unique patch report train",
+            "deb5f45449e615feff5640f2e49f933ff51895de3b4381832b3139941c57b59205a42480c52175b6efcffaa58a2503887c1e8b363a707256bdd2b587b46541f5",
+        ),
+        (
+            "8197a4a47f0425faeaa69deebc05ca29c0a5b5cc76ceacc0",
+            "light rule cinnamon wrap drastic word pride squirrel upgrade then income fatal apart sustain crack supply proud access",
+            "4cbdff1ca2db800fd61cae72a57475fdc6bab03e441fd63f96dabd1f183ef5b782925f00105f318309a7e9c3ea6967c7801e46c8a58082674c860a37b93eda02",
+        ),
+        (
+            "066dca1a2bb7e8a1db2832148ce9933eea0f3ac9548d793112d9a95c9407efad",
+            "all hour make first leader extend hole alien behind guard gospel lava path output census museum junior mass reopen famous sing advance salt reform",
+            "26e975ec644423f4a4c4f4215ef09b4bd7ef924e85d1d17c4cf3f136c2863cf6df0a475045652c57eb5fb41513ca2a2d67722b77e954b4b3fc11f7590449191d",
+        ),
+        (
+            "f30f8c1da665478f49b001d94c5fc452",
+            "vessel ladder alter error federal sibling chat ability sun glass valve picture",
+            "2aaa9242daafcee6aa9d7269f17d4efe271e1b9a529178d7dc139cd18747090bf9d60295d0ce74309a78852a9caadf0af48aae1c6253839624076224374bc63f",
+        ),
+        (
+            "c10ec20dc3cd9f652c7fac2f1230f7a3c828389a14392f05",
+            "scissors invite lock maple supreme raw rapid void congress muscle digital elegant little brisk hair mango congress clump",
+            "7b4a10be9d98e6cba265566db7f136718e1398c71cb581e1b2f464cac1ceedf4f3e274dc270003c670ad8d02c4558b2f8e39edea2775c9e232c7cb798b069e88",
+        ),
+        (
+            "f585c11aec520db57dd353c69554b21a89b20fb0650966fa0a9d6f74fd989d8f",
+            "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold",
+            "01f5bced59dec48e362f2c45b5de68b9fd6c92c6634f44d6d40aab69056506f0e35524a518034ddc1192e1dacd32c1ed3eaa3c3b131c88ed8e7e54c49a5d0998",
+        ),
+    ];
+
+    // Test vector 1 of BIP32 as (path, depth, index, chain code, private key).
+    const BIP32_VECTOR_1: [(&str, u8, u32, &str, &str); 6] = [
+        (
+            "m",
+            0,
+            0,
+            "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+        ),
+        (
+            "m/0'",
+            1,
+            ExtendedPrivateKey::HARDENED,
+            "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+        ),
+        (
+            "m/0'/1",
+            2,
+            1,
+            "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+        ),
+        (
+            "m/0'/1/2'",
+            3,
+            ExtendedPrivateKey::HARDENED | 2,
+            "04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f",
+            "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+        ),
+        (
+            "m/0'/1/2'/2",
+            4,
+            2,
+            "cfb71883f01676f587d023cc53a35bc7f88f724b1f8c2892ac1275ac822a3edd",
+            "0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4",
+        ),
+        (
+            "m/0'/1/2'/2/1000000000",
+            5,
+            1_000_000_000,
+            "c783e67b921d2beb8f6b389cc646d7263b4145701dadd2161548a8b078e65e9e",
+            "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+        ),
+    ];
+
+    #[test]
+    fn mnemonics_match_the_bip39_vectors() {
+        for (entropy, phrase, seed) in BIP39_VECTORS {
+            let entropy = hex::decode(entropy).unwrap();
+            let words = entropy.len() / 4 * 3;
+            let mnemonic = Mnemonic::generate(FixedRng(entropy), words).unwrap();
+
+            assert_eq!(mnemonic.phrase().as_str(), phrase);
+            assert_eq!(mnemonic.word_count(), words);
+            assert_eq!(hex::encode(mnemonic.to_seed("TREZOR").as_ref()), seed);
+
+            let parsed = Mnemonic::parse(phrase).unwrap();
+
+            assert_eq!(hex::encode(parsed.to_seed("TREZOR").as_ref()), seed);
+        }
+    }
+
+    #[test]
+    fn derivation_matches_bip32_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedPrivateKey::from_seed(&seed).unwrap();
+
+        for (path, depth, index, chain_code, key) in BIP32_VECTOR_1 {
+            let derived = master.derive_path(path).unwrap();
+
+            assert_eq!(derived.depth(), depth, "{}", path);
+            assert_eq!(derived.index(), index, "{}", path);
+            assert_eq!(hex::encode(derived.chain_code()), chain_code, "{}", path);
+            assert_eq!(
+                hex::encode(derived.private_key().to_bytes().as_ref()),
+                key,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_paths_are_rejected() {
+        let master = ExtendedPrivateKey::from_seed(&[0; 16]).unwrap();
+
+        for path in ["", "0/1", "m/x", "m/2147483648", "m/2147483648'"] {
+            assert!(master.derive_path(path).is_none(), "{}", path);
+        }
+    }
+}
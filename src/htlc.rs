@@ -0,0 +1,68 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{util::SerdeVisitor, Hash, HashError};
+
+// The secret that unlocks a hash time-locked contract. Contracts are locked to its SHA-256 hash,
+// as on other chains, so that a swap can use the same secret on both sides.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Preimage([u8; 32]);
+
+impl Preimage {
+    pub fn random(mut rng: impl rand_core::CryptoRng + rand_core::RngCore) -> Self {
+        let mut bytes = [0; 32];
+
+        rng.fill_bytes(&mut bytes);
+
+        Self(bytes)
+    }
+
+    pub fn hash_lock(&self) -> Hash {
+        Hash::new(Sha256::digest(self.0).into())
+    }
+}
+
+impl FromStr for Preimage {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.parse::<Hash>()?;
+
+        Ok(Self(*bytes.bytes()))
+    }
+}
+
+impl fmt::Display for Preimage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&Hash::new(self.0), f)
+    }
+}
+
+impl fmt::Debug for Preimage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Preimage({})", self)
+    }
+}
+
+impl Serialize for Preimage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Preimage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = deserializer.deserialize_str(SerdeVisitor)?;
+
+        Self::from_str(&s)
+            .map_err(|error| de::Error::custom(format!("invalid preimage: {}", error)))
+    }
+}
@@ -1,56 +1,267 @@
-use hex::{FromHex, ToHex};
+use std::{fmt, str::FromStr};
+
+use hex::{FromHex, FromHexError, ToHex};
 use serde::{de, Deserialize, Serialize};
 
 use crate::{util::SerdeVisitor, Hash};
 
-#[derive(Debug, Clone, Eq)]
-pub struct PublicKey(k256::ecdsa::VerifyingKey);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    Ecdsa,
+    Schnorr,
+}
 
 #[derive(Debug, Clone, Eq)]
-pub struct PrivateKey(k256::ecdsa::SigningKey);
+pub struct PublicKey(PublicKeyInner);
+
+// The signing key zeroizes itself when dropped, and private keys are only serialized through
+// `private_key_serde`.
+#[derive(Clone, Eq)]
+pub struct PrivateKey {
+    key: k256::ecdsa::SigningKey,
+    scheme: Scheme,
+}
 
 #[derive(Debug, Clone, Eq)]
-pub struct Signature(k256::ecdsa::Signature);
+pub struct Signature(SignatureInner);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemeError {
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublicKeyError {
+    Hex(FromHexError),
+    Length(usize),
+    InvalidPoint,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrivateKeyError {
+    Hex(FromHexError),
+    Length(usize),
+    InvalidScalar,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureError {
+    Hex(FromHexError),
+    Length(usize),
+    InvalidEncoding,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PublicKeyInner {
+    Ecdsa(k256::ecdsa::VerifyingKey),
+    Schnorr(k256::schnorr::VerifyingKey),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SignatureInner {
+    Ecdsa(k256::ecdsa::Signature),
+    Schnorr(k256::schnorr::Signature),
+}
+
+impl Scheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ecdsa => "ecdsa",
+            Self::Schnorr => "schnorr",
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Scheme {
+    type Error = SchemeError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        [Self::Ecdsa, Self::Schnorr]
+            .into_iter()
+            .find(|scheme| scheme.name() == value)
+            .ok_or_else(|| SchemeError::Unknown(value.into()))
+    }
+}
 
 impl PublicKey {
+    pub fn scheme(&self) -> Scheme {
+        match self.0 {
+            PublicKeyInner::Ecdsa(_) => Scheme::Ecdsa,
+            PublicKeyInner::Schnorr(_) => Scheme::Schnorr,
+        }
+    }
+
     pub fn verify(&self, hash: &Hash, signature: &Signature) -> bool {
         use k256::ecdsa::signature::Verifier;
 
-        self.0.verify(hash.bytes(), &signature.0).is_ok()
+        match (&self.0, &signature.0) {
+            (PublicKeyInner::Ecdsa(key), SignatureInner::Ecdsa(signature)) => {
+                key.verify(hash.bytes(), signature).is_ok()
+            }
+            (PublicKeyInner::Schnorr(key), SignatureInner::Schnorr(signature)) => {
+                key.verify_prehashed(hash.bytes(), signature).is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    // Schnorr signatures are checked together as in BIP340 batch verification, a single random
+    // linear combination of all equations, while ECDSA signatures are checked one at a time.
+    pub fn verify_batch(
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        items: &[(&PublicKey, &Hash, &Signature)],
+    ) -> bool {
+        use k256::{
+            elliptic_curve::{
+                bigint::U256,
+                ops::{LinearCombination, Reduce},
+                DecompactPoint, Field, PrimeField,
+            },
+            AffinePoint, FieldBytes, ProjectivePoint, Scalar,
+        };
+        use sha2::{Digest, Sha256};
+
+        let tag = Sha256::digest(b"BIP0340/challenge");
+
+        let mut scalar = Scalar::ZERO;
+        let mut point = ProjectivePoint::IDENTITY;
+        let mut first = true;
+
+        for (key, hash, signature) in items {
+            let (PublicKeyInner::Schnorr(key), SignatureInner::Schnorr(signature)) =
+                (&key.0, &signature.0)
+            else {
+                if !key.verify(hash, signature) {
+                    return false;
+                }
+
+                continue;
+            };
+
+            let bytes = signature.as_bytes();
+
+            let Some(r) = Option::<AffinePoint>::from(AffinePoint::decompact(
+                FieldBytes::from_slice(&bytes[..32]),
+            )) else {
+                return false;
+            };
+
+            let Some(s) =
+                Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(&bytes[32..])))
+            else {
+                return false;
+            };
+
+            let e = <Scalar as Reduce<U256>>::from_be_bytes_reduced(
+                Sha256::new()
+                    .chain_update(tag)
+                    .chain_update(tag)
+                    .chain_update(&bytes[..32])
+                    .chain_update(key.to_bytes())
+                    .chain_update(hash.bytes())
+                    .finalize(),
+            );
+
+            let a = if first {
+                Scalar::ONE
+            } else {
+                Scalar::random(&mut rng)
+            };
+
+            first = false;
+
+            scalar += a * s;
+            point += ProjectivePoint::lincomb(
+                &ProjectivePoint::from(r),
+                &a,
+                &ProjectivePoint::from(*key.as_affine()),
+                &(a * e),
+            );
+        }
+
+        ProjectivePoint::GENERATOR * scalar == point
+    }
+
+    // x-only Schnorr keys stand for the point with an even y coordinate.
+    fn to_point(&self) -> k256::ProjectivePoint {
+        match &self.0 {
+            PublicKeyInner::Ecdsa(key) => key.into(),
+            PublicKeyInner::Schnorr(key) => (*key.as_affine()).into(),
+        }
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        match &self.0 {
+            PublicKeyInner::Ecdsa(key) => key.to_bytes().to_vec(),
+            PublicKeyInner::Schnorr(key) => key.to_bytes().to_vec(),
+        }
+    }
+
+    // 32-byte x-only Schnorr keys, and compressed or uncompressed SEC1 ECDSA keys.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, PublicKeyError> {
+        match bytes.len() {
+            32 => {
+                let Ok(key) = k256::schnorr::VerifyingKey::from_bytes(bytes) else {
+                    return Err(PublicKeyError::InvalidPoint);
+                };
+
+                Ok(Self(PublicKeyInner::Schnorr(key)))
+            }
+            33 | 65 => {
+                let Ok(key) = k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes) else {
+                    return Err(PublicKeyError::InvalidPoint);
+                };
+
+                Ok(Self(PublicKeyInner::Ecdsa(key)))
+            }
+            length => Err(PublicKeyError::Length(length)),
+        }
     }
 }
 
 impl From<&PrivateKey> for PublicKey {
     fn from(key: &PrivateKey) -> Self {
-        Self(key.0.verifying_key())
+        match key.scheme {
+            Scheme::Ecdsa => Self(PublicKeyInner::Ecdsa(key.key.verifying_key())),
+            Scheme::Schnorr => Self(PublicKeyInner::Schnorr(*key.schnorr().verifying_key())),
+        }
     }
 }
 
 impl From<&PublicKey> for String {
     fn from(key: &PublicKey) -> Self {
-        key.0.to_bytes().encode_hex::<String>()
+        key.to_bytes().encode_hex::<String>()
     }
 }
 
 impl<'a> TryFrom<&'a str> for PublicKey {
-    type Error = ();
+    type Error = PublicKeyError;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let Ok(bytes) = Vec::<u8>::from_hex(value) else {
-            return Err(());
-        };
+        let bytes = Vec::<u8>::from_hex(value).map_err(PublicKeyError::Hex)?;
 
-        let Ok(key) = k256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes) else {
-            return Err(());
-        };
+        Self::from_bytes(&bytes)
+    }
+}
 
-        Ok(Self(key))
+impl FromStr for PublicKey {
+    type Err = PublicKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from(self))
     }
 }
 
 impl std::hash::Hash for PublicKey {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let bytes = self.0.to_bytes();
+        let bytes = self.to_bytes();
 
         state.write(&bytes);
     }
@@ -64,95 +275,223 @@ impl std::cmp::PartialEq for PublicKey {
 
 impl PrivateKey {
     pub fn random(rng: impl rand_core::CryptoRng + rand_core::RngCore) -> Self {
-        Self(k256::ecdsa::SigningKey::random(rng))
+        Self {
+            key: k256::ecdsa::SigningKey::random(rng),
+            scheme: Scheme::Ecdsa,
+        }
+    }
+
+    pub fn with_scheme(self, scheme: Scheme) -> Self {
+        Self { scheme, ..self }
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, PrivateKeyError> {
+        if bytes.len() != 32 {
+            return Err(PrivateKeyError::Length(bytes.len()));
+        }
+
+        let Ok(key) = k256::ecdsa::SigningKey::from_bytes(bytes) else {
+            return Err(PrivateKeyError::InvalidScalar);
+        };
+
+        Ok(Self {
+            key,
+            scheme: Scheme::Ecdsa,
+        })
+    }
+
+    pub(crate) fn to_bytes(&self) -> zeroize::Zeroizing<[u8; 32]> {
+        zeroize::Zeroizing::new(self.key.to_bytes().into())
+    }
+
+    // The x coordinate of the point agreed on with the holder of the other key through ECDH. Both
+    // points an x-only Schnorr key could stand for share it, so the scheme of either key is
+    // irrelevant.
+    pub(crate) fn shared_secret(&self, key: &PublicKey) -> zeroize::Zeroizing<[u8; 32]> {
+        use k256::elliptic_curve::AffineXCoordinate;
+
+        let secret = k256::SecretKey::from_be_bytes(self.to_bytes().as_ref())
+            .expect("signing keys are valid secret keys");
+
+        let point = key.to_point() * *secret.to_nonzero_scalar();
+
+        zeroize::Zeroizing::new(point.to_affine().x().into())
+    }
+
+    fn schnorr(&self) -> k256::schnorr::SigningKey {
+        k256::schnorr::SigningKey::from_bytes(self.to_bytes().as_ref())
+            .expect("ECDSA signing keys are valid Schnorr signing keys")
     }
 
     pub fn sign(&self, hash: &Hash) -> Option<Signature> {
         use k256::ecdsa::signature::Signer;
 
-        let Ok(signature) = self.0.try_sign(hash.bytes()) else {
-            return None;
-        };
+        match self.scheme {
+            Scheme::Ecdsa => {
+                let Ok(signature) = self.key.try_sign(hash.bytes()) else {
+                    return None;
+                };
 
-        Some(Signature(signature))
+                Some(Signature(SignatureInner::Ecdsa(signature)))
+            }
+            Scheme::Schnorr => self.sign_schnorr(hash, &[0; 32]),
+        }
     }
 
     pub fn sign_with_rng(
         &self,
-        rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
         hash: &Hash,
     ) -> Option<Signature> {
         use k256::ecdsa::signature::RandomizedSigner;
 
-        let Ok(signature) = self.0.try_sign_with_rng(rng, hash.bytes()) else {
+        match self.scheme {
+            Scheme::Ecdsa => {
+                let Ok(signature) = self.key.try_sign_with_rng(rng, hash.bytes()) else {
+                    return None;
+                };
+
+                Some(Signature(SignatureInner::Ecdsa(signature)))
+            }
+            Scheme::Schnorr => {
+                let mut aux = [0; 32];
+
+                rng.fill_bytes(&mut aux);
+
+                self.sign_schnorr(hash, &aux)
+            }
+        }
+    }
+
+    fn sign_schnorr(&self, hash: &Hash, aux: &[u8; 32]) -> Option<Signature> {
+        let Ok(signature) = self.schnorr().try_sign_prehashed(hash.bytes(), aux) else {
             return None;
         };
 
-        Some(Signature(signature))
+        Some(Signature(SignatureInner::Schnorr(signature)))
     }
 }
 
 impl From<&PrivateKey> for String {
     fn from(key: &PrivateKey) -> Self {
-        key.0.to_bytes().encode_hex::<String>()
+        key.to_bytes().encode_hex::<String>()
     }
 }
 
 impl<'a> TryFrom<&'a str> for PrivateKey {
-    type Error = ();
+    type Error = PrivateKeyError;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let Ok(bytes) = Vec::<u8>::from_hex(value) else {
-            return Err(());
-        };
+        let bytes = Vec::<u8>::from_hex(value).map_err(PrivateKeyError::Hex)?;
 
-        let Ok(key) = k256::ecdsa::SigningKey::from_bytes(&bytes) else {
-            return Err(());
-        };
-
-        Ok(Self(key))
+        Self::from_bytes(&zeroize::Zeroizing::new(bytes))
     }
 }
 
-impl std::hash::Hash for PrivateKey {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let bytes = self.0.to_bytes();
+impl zeroize::ZeroizeOnDrop for PrivateKey {}
 
-        state.write(&bytes);
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("public_key", &String::from(&PublicKey::from(self)))
+            .field("scheme", &self.scheme)
+            .finish_non_exhaustive()
     }
 }
 
 impl std::cmp::PartialEq for PrivateKey {
     fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+        use subtle::ConstantTimeEq;
+
+        let same_key = self.to_bytes().ct_eq(other.to_bytes().as_ref());
+
+        bool::from(same_key) && self.scheme == other.scheme
+    }
+}
+
+impl Signature {
+    // Fills the signature field of data that is yet to be signed; it never verifies.
+    pub fn placeholder() -> Self {
+        let mut one = k256::FieldBytes::default();
+
+        one[31] = 1;
+
+        let signature = k256::ecdsa::Signature::from_scalars(one, one)
+            .expect("one is a valid signature scalar");
+
+        Self(SignatureInner::Ecdsa(signature))
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        match self.0 {
+            SignatureInner::Ecdsa(_) => Scheme::Ecdsa,
+            SignatureInner::Schnorr(_) => Scheme::Schnorr,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match &self.0 {
+            SignatureInner::Ecdsa(signature) => signature.to_der().as_bytes().to_vec(),
+            SignatureInner::Schnorr(signature) => signature.as_bytes().to_vec(),
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SignatureError> {
+        // DER encodings of secp256k1 signatures take between 8 and 72 bytes.
+        if !(8..=72).contains(&bytes.len()) {
+            return Err(SignatureError::Length(bytes.len()));
+        }
+
+        // A DER encoding can also be 64 bytes long, so it takes precedence over the raw Schnorr form.
+        if let Ok(signature) = k256::ecdsa::Signature::from_der(bytes) {
+            return Ok(Self(SignatureInner::Ecdsa(signature)));
+        }
+
+        let Ok(signature) = k256::schnorr::Signature::try_from(bytes) else {
+            return Err(SignatureError::InvalidEncoding);
+        };
+
+        Ok(Self(SignatureInner::Schnorr(signature)))
     }
 }
 
 impl From<&Signature> for String {
     fn from(signature: &Signature) -> Self {
-        signature.0.to_der().as_bytes().encode_hex::<String>()
+        signature.to_bytes().encode_hex::<String>()
     }
 }
 
 impl<'a> TryFrom<&'a str> for Signature {
-    type Error = ();
+    type Error = SignatureError;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let Ok(bytes) = Vec::<u8>::from_hex(value) else {
-            return Err(());
-        };
+        let bytes = Vec::<u8>::from_hex(value).map_err(SignatureError::Hex)?;
 
-        let Ok(key) = k256::ecdsa::Signature::from_der(&bytes) else {
-            return Err(());
-        };
+        Self::from_bytes(&bytes)
+    }
+}
 
-        Ok(Self(key))
+impl FromStr for Signature {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from(self))
     }
 }
 
 impl std::hash::Hash for Signature {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        state.write(self.0.to_der().as_bytes());
+        state.write(&self.to_bytes());
     }
 }
 
@@ -162,25 +501,58 @@ impl std::cmp::PartialEq for Signature {
     }
 }
 
-impl Serialize for PublicKey {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let bytes = self.0.to_bytes();
+impl fmt::Display for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown signature scheme {}", name),
+        }
+    }
+}
+
+impl std::error::Error for SchemeError {}
 
-        serializer.serialize_str(&bytes.encode_hex::<String>())
+impl fmt::Display for PublicKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex(error) => write!(f, "invalid hex ({})", error),
+            Self::Length(length) => write!(f, "expected 32, 33 or 65 bytes, got {}", length),
+            Self::InvalidPoint => f.write_str("not a point on the secp256k1 curve"),
+        }
     }
 }
 
-impl Serialize for PrivateKey {
+impl std::error::Error for PublicKeyError {}
+
+impl fmt::Display for PrivateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex(error) => write!(f, "invalid hex ({})", error),
+            Self::Length(length) => write!(f, "expected 32 bytes, got {}", length),
+            Self::InvalidScalar => f.write_str("out of range for secp256k1"),
+        }
+    }
+}
+
+impl std::error::Error for PrivateKeyError {}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex(error) => write!(f, "invalid hex ({})", error),
+            Self::Length(length) => write!(f, "expected 8 to 72 bytes, got {}", length),
+            Self::InvalidEncoding => f.write_str("neither a DER nor a BIP340 signature"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl Serialize for PublicKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let bytes = self.0.to_bytes();
-
-        serializer.serialize_str(&bytes.encode_hex::<String>())
+        serializer.serialize_str(&String::from(self))
     }
 }
 
@@ -189,9 +561,7 @@ impl Serialize for Signature {
     where
         S: serde::Serializer,
     {
-        let s = self.0.to_der().as_bytes().encode_hex::<String>();
-
-        serializer.serialize_str(&s)
+        serializer.serialize_str(&String::from(self))
     }
 }
 
@@ -202,40 +572,282 @@ impl<'de> Deserialize<'de> for PublicKey {
     {
         let s = deserializer.deserialize_str(SerdeVisitor)?;
 
-        let bytes = Vec::<u8>::from_hex(&s).map_err(de::Error::custom)?;
-
-        let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes).map_err(de::Error::custom)?;
-
-        Ok(Self(key))
+        Self::try_from(s.as_str())
+            .map_err(|error| de::Error::custom(format!("invalid public key: {}", error)))
     }
 }
 
-impl<'de> Deserialize<'de> for PrivateKey {
+impl<'de> Deserialize<'de> for Signature {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
         let s = deserializer.deserialize_str(SerdeVisitor)?;
 
-        let bytes = Vec::<u8>::from_hex(&s).map_err(de::Error::custom)?;
+        Self::try_from(s.as_str())
+            .map_err(|error| de::Error::custom(format!("invalid signature: {}", error)))
+    }
+}
 
-        let key = k256::ecdsa::SigningKey::from_bytes(&bytes).map_err(de::Error::custom)?;
+// Serializes a private key as plain hex, for the fields that really need to store one:
+// `#[serde(with = "farcoin::private_key_serde")]`.
+pub mod private_key_serde {
+    use serde::de;
+    use zeroize::Zeroizing;
 
-        Ok(Self(key))
+    use super::PrivateKey;
+    use crate::util::SerdeVisitor;
+
+    pub fn serialize<S>(key: &PrivateKey, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&Zeroizing::new(String::from(key)))
     }
-}
 
-impl<'de> Deserialize<'de> for Signature {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<PrivateKey, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let s = deserializer.deserialize_str(SerdeVisitor)?;
+        let s = Zeroizing::new(deserializer.deserialize_str(SerdeVisitor)?);
 
-        let bytes = Vec::<u8>::from_hex(&s).map_err(de::Error::custom)?;
+        PrivateKey::try_from(s.as_str())
+            .map_err(|error| de::Error::custom(format!("invalid private key: {}", error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The BIP340 test vectors as (index, public key, message, signature, valid).
+    const BIP340_VECTORS: [(u8, &str, &str, &str, bool); 15] = [
+        (
+            0,
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0",
+            true,
+        ),
+        (
+            1,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de33418906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a",
+            true,
+        ),
+        (
+            2,
+            "dd308afec5777e13121fa72b9cc1b7cc0139715309b086c960e18fd969774eb8",
+            "7e2d58d8b3bcdf1abadec7829054f90dda9805aab56c77333024b9d0a508b75c",
+            "5831aaeed7b44bb74e5eab94ba9d4294c49bcf2a60728d8b4c200f50dd313c1bab745879a5ad954a72c45a91c3a51d3c7adea98d82f8481e0e1e03674a6f3fb7",
+            true,
+        ),
+        (
+            3,
+            "25d1dff95105f5253c4022f628a996ad3a0d95fbf21d468a1b33f8c160d8f517",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "7eb0509757e246f19449885651611cb965ecc1a187dd51b64fda1edc9637d5ec97582b9cb13db3933705b32ba982af5af25fd78881ebb32771fc5922efc66ea3",
+            true,
+        ),
+        (
+            4,
+            "d69c3509bb99e412e68b0fe8544e72837dfa30746d8be2aa65975f29d22dc7b9",
+            "4df3c3f68fcc83b27e9d42c90431a72499f17875c81a599b566c9889b9696703",
+            "00000000000000000000003b78ce563f89a0ed9414f5aa28ad0d96d6795f9c6376afb1548af603b3eb45c9f8207dee1060cb71c04e80f593060b07d28308d7f4",
+            true,
+        ),
+        (
+            5,
+            "eefdea4cdb677750a420fee807eacf21eb9898ae79b9768766e4faa04a2d4a34",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e17776969e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+            false,
+        ),
+        (
+            6,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "fff97bd5755eeea420453a14355235d382f6472f8568a18b2f057a14602975563cc27944640ac607cd107ae10923d9ef7a73c643e166be5ebeafa34b1ac553e2",
+            false,
+        ),
+        (
+            7,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "1fa62e331edbc21c394792d2ab1100a7b432b013df3f6ff4f99fcb33e0e1515f28890b3edb6e7189b630448b515ce4f8622a954cfe545735aaea5134fccdb2bd",
+            false,
+        ),
+        (
+            8,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e177769961764b3aa9b2ffcb6ef947b6887a226e8d7c93e00c5ed0c1834ff0d0c2e6da6",
+            false,
+        ),
+        (
+            9,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "0000000000000000000000000000000000000000000000000000000000000000123dda8328af9c23a94c1feecfd123ba4fb73476f0d594dcb65c6425bd186051",
+            false,
+        ),
+        (
+            10,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "00000000000000000000000000000000000000000000000000000000000000017615fbaf5ae28864013c099742deadb4dba87f11ac6754f93780d5a1837cf197",
+            false,
+        ),
+        (
+            11,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "4a298dacae57395a15d0795ddbfd1dcb564da82b0f269bc70a74f8220429ba1d69e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+            false,
+        ),
+        (
+            12,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f69e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+            false,
+        ),
+        (
+            13,
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e177769fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            false,
+        ),
+        (
+            14,
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc30",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e17776969e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+            false,
+        ),
+    ];
+
+    // Returns None where the vector has a key or signature that does not even parse.
+    fn vector(
+        public_key: &str,
+        message: &str,
+        signature: &str,
+    ) -> Option<(PublicKey, Hash, Signature)> {
+        let public_key = PublicKey::try_from(public_key).ok()?;
+        let message = Hash::new(<[u8; 32]>::from_hex(message).ok()?);
+        let signature = Signature::try_from(signature).ok()?;
+
+        Some((public_key, message, signature))
+    }
+
+    #[test]
+    fn schnorr_verification_matches_bip340() {
+        for (index, public_key, message, signature, valid) in BIP340_VECTORS {
+            let Some((key, hash, signature)) = vector(public_key, message, signature) else {
+                assert!(!valid, "vector {} does not parse", index);
+                continue;
+            };
+
+            assert_eq!(key.scheme(), Scheme::Schnorr, "vector {}", index);
+            assert_eq!(signature.scheme(), Scheme::Schnorr, "vector {}", index);
+            assert_eq!(key.verify(&hash, &signature), valid, "vector {}", index);
+            assert_eq!(
+                PublicKey::verify_batch(rand_core::OsRng, &[(&key, &hash, &signature)]),
+                valid,
+                "vector {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn batches_fail_on_any_invalid_bip340_vector() {
+        let vectors: Vec<_> = BIP340_VECTORS
+            .iter()
+            .filter_map(|(_, key, message, signature, valid)| {
+                Some((vector(key, message, signature)?, *valid))
+            })
+            .collect();
+
+        let valid: Vec<_> = vectors
+            .iter()
+            .filter(|(_, valid)| *valid)
+            .map(|((key, hash, signature), _)| (key, hash, signature))
+            .collect();
+
+        assert_eq!(valid.len(), 5);
+        assert!(PublicKey::verify_batch(rand_core::OsRng, &valid));
+
+        for ((key, hash, signature), _) in vectors.iter().filter(|(_, valid)| !*valid) {
+            let mut items = valid.clone();
+
+            items.insert(2, (key, hash, signature));
+
+            assert!(!PublicKey::verify_batch(rand_core::OsRng, &items));
+        }
+    }
+
+    #[test]
+    fn batches_with_one_bad_signature_fail() {
+        let keys: Vec<_> = (0..8)
+            .map(|i| {
+                let scheme = if i % 4 == 3 {
+                    Scheme::Ecdsa
+                } else {
+                    Scheme::Schnorr
+                };
+
+                PrivateKey::random(rand_core::OsRng).with_scheme(scheme)
+            })
+            .collect();
+
+        let hashes: Vec<_> = (0..8u8).map(|i| Hash::new([i; 32])).collect();
+
+        let public_keys: Vec<_> = keys.iter().map(PublicKey::from).collect();
+
+        let signatures: Vec<_> = keys
+            .iter()
+            .zip(&hashes)
+            .map(|(key, hash)| key.sign(hash).unwrap())
+            .collect();
+
+        let verify = |signatures: &[Signature]| -> bool {
+            let items: Vec<_> = public_keys
+                .iter()
+                .zip(&hashes)
+                .zip(signatures)
+                .map(|((key, hash), signature)| (key, hash, signature))
+                .collect();
+
+            PublicKey::verify_batch(rand_core::OsRng, &items)
+        };
+
+        assert!(verify(&signatures));
+
+        for bad in 0..keys.len() {
+            let mut tampered = signatures.clone();
+
+            // A valid signature by the same key, but over another hash.
+            tampered[bad] = keys[bad].sign(&Hash::new([0xff; 32])).unwrap();
+
+            assert!(!verify(&tampered), "signature {}", bad);
+        }
+
+        let mut swapped = signatures.clone();
+
+        swapped.swap(0, 1);
+
+        assert!(!verify(&swapped));
+    }
 
-        let signature = k256::ecdsa::Signature::from_der(&bytes).map_err(de::Error::custom)?;
+    #[test]
+    fn private_keys_are_not_debug_printed() {
+        let key = PrivateKey::random(rand_core::OsRng);
+        let printed = format!("{:?}", key);
 
-        Ok(Self(signature))
+        assert!(!printed.contains(&String::from(&key)));
+        assert!(printed.contains(&String::from(&PublicKey::from(&key))));
     }
 }
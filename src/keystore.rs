@@ -0,0 +1,337 @@
+use std::{fmt, fs, io, io::Write, path::Path};
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{PrivateKey, PublicKey};
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+    UnsupportedAlgorithm(String),
+    InvalidParams,
+    Decryption,
+    InvalidKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub memory: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    #[serde(with = "hex::serde")]
+    pub salt: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub algorithm: String,
+    #[serde(with = "hex::serde")]
+    pub nonce: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub public_key: PublicKey,
+    pub kdf: KdfParams,
+    pub cipher: CipherParams,
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl KdfParams {
+    pub const ARGON2ID: &str = "argon2id";
+
+    // Keystore files may come from anywhere, so their cost is capped before anything is allocated.
+    // The memory is in KiB, so at most 1 GiB.
+    pub const MAX_MEMORY: u32 = 1 << 20;
+    pub const MAX_ITERATIONS: u32 = 64;
+    pub const MAX_PARALLELISM: u32 = 16;
+
+    pub fn new(rng: impl rand_core::CryptoRng + rand_core::RngCore) -> Self {
+        let params = argon2::Params::default();
+
+        Self::with_cost(rng, params.m_cost(), params.t_cost(), params.p_cost())
+    }
+
+    pub fn with_cost(
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        memory: u32,
+        iterations: u32,
+        parallelism: u32,
+    ) -> Self {
+        let mut salt = vec![0; 16];
+
+        rng.fill_bytes(&mut salt);
+
+        Self {
+            algorithm: Self::ARGON2ID.into(),
+            memory,
+            iterations,
+            parallelism,
+            salt,
+        }
+    }
+
+    fn derive(&self, password: &str) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+        if self.algorithm != Self::ARGON2ID {
+            return Err(KeystoreError::UnsupportedAlgorithm(self.algorithm.clone()));
+        }
+
+        if self.memory > Self::MAX_MEMORY
+            || self.iterations > Self::MAX_ITERATIONS
+            || self.parallelism > Self::MAX_PARALLELISM
+        {
+            return Err(KeystoreError::InvalidParams);
+        }
+
+        let params = argon2::Params::new(self.memory, self.iterations, self.parallelism, Some(32))
+            .map_err(|_| KeystoreError::InvalidParams)?;
+
+        let argon2 =
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+
+        let mut key = Zeroizing::new([0; 32]);
+
+        argon2
+            .hash_password_into(password.as_bytes(), &self.salt, key.as_mut())
+            .map_err(|_| KeystoreError::InvalidParams)?;
+
+        Ok(key)
+    }
+}
+
+impl Keystore {
+    pub const VERSION: u32 = 1;
+    pub const XCHACHA20POLY1305: &str = "xchacha20poly1305";
+
+    pub fn encrypt(
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        key: &PrivateKey,
+        password: &str,
+    ) -> Result<Self, KeystoreError> {
+        let kdf = KdfParams::new(&mut rng);
+
+        Self::encrypt_with_params(rng, key, password, kdf)
+    }
+
+    pub fn encrypt_with_params(
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        key: &PrivateKey,
+        password: &str,
+        kdf: KdfParams,
+    ) -> Result<Self, KeystoreError> {
+        let mut nonce = vec![0; 24];
+
+        rng.fill_bytes(&mut nonce);
+
+        let mut keystore = Self {
+            version: Self::VERSION,
+            public_key: PublicKey::from(key),
+            kdf,
+            cipher: CipherParams {
+                algorithm: Self::XCHACHA20POLY1305.into(),
+                nonce,
+            },
+            ciphertext: vec![],
+        };
+
+        let cipher = keystore.cipher(password)?;
+        let bytes = key.to_bytes();
+        let aad = keystore.associated_data()?;
+
+        let payload = Payload {
+            msg: bytes.as_ref(),
+            aad: &aad,
+        };
+
+        keystore.ciphertext = cipher
+            .encrypt(XNonce::from_slice(&keystore.cipher.nonce), payload)
+            .map_err(|_| KeystoreError::InvalidParams)?;
+
+        Ok(keystore)
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey, KeystoreError> {
+        if self.version != Self::VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+
+        if self.cipher.nonce.len() != 24 {
+            return Err(KeystoreError::InvalidParams);
+        }
+
+        let cipher = self.cipher(password)?;
+        let aad = self.associated_data()?;
+
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &aad,
+        };
+
+        let bytes = Zeroizing::new(
+            cipher
+                .decrypt(XNonce::from_slice(&self.cipher.nonce), payload)
+                .map_err(|_| KeystoreError::Decryption)?,
+        );
+
+        let Ok(key) = PrivateKey::from_bytes(&bytes) else {
+            return Err(KeystoreError::InvalidKey);
+        };
+
+        let key = key.with_scheme(self.public_key.scheme());
+
+        if PublicKey::from(&key) != self.public_key {
+            return Err(KeystoreError::InvalidKey);
+        }
+
+        Ok(key)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KeystoreError> {
+        let contents = fs::read_to_string(path).map_err(KeystoreError::Io)?;
+
+        serde_json::from_str(&contents).map_err(KeystoreError::Format)
+    }
+
+    // Only the owner may read the file, even when it replaces an existing one.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KeystoreError> {
+        let contents = serde_json::to_string_pretty(self).map_err(KeystoreError::Format)?;
+
+        let mut options = fs::OpenOptions::new();
+
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path).map_err(KeystoreError::Io)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            file.set_permissions(fs::Permissions::from_mode(0o600))
+                .map_err(KeystoreError::Io)?;
+        }
+
+        file.write_all(contents.as_bytes())
+            .map_err(KeystoreError::Io)
+    }
+
+    fn cipher(&self, password: &str) -> Result<XChaCha20Poly1305, KeystoreError> {
+        if self.cipher.algorithm != Self::XCHACHA20POLY1305 {
+            return Err(KeystoreError::UnsupportedAlgorithm(
+                self.cipher.algorithm.clone(),
+            ));
+        }
+
+        let key = self.kdf.derive(password)?;
+
+        XChaCha20Poly1305::new_from_slice(key.as_ref()).map_err(|_| KeystoreError::InvalidParams)
+    }
+
+    fn associated_data(&self) -> Result<Vec<u8>, KeystoreError> {
+        let header = serde_json::json!({
+            "version": self.version,
+            "public_key": self.public_key,
+            "kdf": self.kdf,
+            "cipher": self.cipher,
+        });
+
+        serde_json::to_vec(&header).map_err(KeystoreError::Format)
+    }
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to access keystore: {}", error),
+            Self::Format(error) => write!(f, "malformed keystore: {}", error),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported keystore version {}", version)
+            }
+            Self::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "unsupported keystore algorithm {}", algorithm)
+            }
+            Self::InvalidParams => f.write_str("invalid keystore parameters"),
+            Self::Decryption => f.write_str("wrong password or corrupted keystore"),
+            Self::InvalidKey => f.write_str("keystore contains an invalid key"),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystore(key: &PrivateKey) -> Keystore {
+        let kdf = KdfParams::with_cost(rand_core::OsRng, 64, 1, 1);
+
+        Keystore::encrypt_with_params(rand_core::OsRng, key, "password", kdf).unwrap()
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        let key = PrivateKey::random(rand_core::OsRng);
+        let keystore = keystore(&key);
+
+        assert_eq!(keystore.decrypt("password").unwrap(), key);
+        assert!(matches!(
+            keystore.decrypt("wrong"),
+            Err(KeystoreError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn excessive_kdf_costs_are_rejected() {
+        let key = PrivateKey::random(rand_core::OsRng);
+
+        for (memory, iterations, parallelism) in [(u32::MAX, 1, 1), (64, u32::MAX, 1), (64, 1, 64)]
+        {
+            let mut keystore = keystore(&key);
+
+            keystore.kdf.memory = memory;
+            keystore.kdf.iterations = iterations;
+            keystore.kdf.parallelism = parallelism;
+
+            assert!(matches!(
+                keystore.decrypt("password"),
+                Err(KeystoreError::InvalidParams)
+            ));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_keystores_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let key = PrivateKey::random(rand_core::OsRng);
+        let path =
+            std::env::temp_dir().join(format!("farcoin-keystore-{}.json", std::process::id()));
+
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        keystore(&key).save(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        let loaded = Keystore::load(&path).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(loaded.decrypt("password").unwrap(), key);
+    }
+}
@@ -1,10 +1,37 @@
+mod address;
+mod amount;
+mod batch;
 mod hash;
+mod hd;
+mod htlc;
 mod key;
+mod keystore;
+mod memo;
+mod multisig;
+mod params;
+mod psbt;
+mod signer;
 mod time;
 pub(crate) mod util;
 
-pub use hash::Hash;
-pub use key::{PrivateKey, PublicKey, Signature};
+pub use address::{Address, AddressError, Network, NetworkError};
+pub use amount::{Amount, AmountError};
+pub use batch::{SignatureBatch, SignedItem};
+pub use hash::{Hash, HashError};
+pub use hd::{ExtendedPrivateKey, Mnemonic};
+pub use htlc::Preimage;
+pub use key::{
+    private_key_serde, PrivateKey, PrivateKeyError, PublicKey, PublicKeyError, Scheme, SchemeError,
+    Signature, SignatureError,
+};
+pub use keystore::{CipherParams, KdfParams, Keystore, KeystoreError};
+pub use memo::{EncryptedMemo, Memo, MemoError};
+pub use multisig::Multisig;
+pub use params::ChainParams;
+pub use psbt::{PartialTransaction, PartialTransactionError};
+pub use signer::Signer;
+#[cfg(unix)]
+pub use signer::{serve_signer, RemoteSigner};
 pub use time::UtcDateTime;
 
 use serde::{Deserialize, Serialize};
@@ -18,13 +45,102 @@ pub enum BlockData {
         signature: Signature,
     },
     Transaction {
-        fee: u64,
-        amount: u64,
+        fee: Amount,
+        amount: Amount,
         time: UtcDateTime,
         sender: PublicKey,
+        receiver: Address,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<Memo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lock: Option<Lock>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expiry: Option<Expiry>,
+        signature: Signature,
+    },
+    // Pays several wallets at once; the sender pays the fee once on top of the outputs.
+    BatchTransaction {
+        fee: Amount,
+        time: UtcDateTime,
+        sender: PublicKey,
+        outputs: Vec<Output>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<Memo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expiry: Option<Expiry>,
+        signature: Signature,
+    },
+    CreateMultisigWallet {
+        id: u64,
+        owners: Multisig,
+        time: UtcDateTime,
+        signatures: Vec<Option<Signature>>,
+    },
+    MultisigTransaction {
+        fee: Amount,
+        amount: Amount,
+        time: UtcDateTime,
+        sender: Multisig,
+        receiver: Address,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<Memo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expiry: Option<Expiry>,
+        signatures: Vec<Option<Signature>>,
+    },
+    // Locks coins (minus the fee) in a hash time-locked contract, identified by the hash of this
+    // data. The receiver can claim them with the preimage of the hash lock until the timeout, after
+    // which the sender can take them back.
+    CreateHtlc {
+        fee: Amount,
+        amount: Amount,
+        time: UtcDateTime,
+        sender: PublicKey,
+        receiver: Address,
+        hash_lock: Hash,
+        timeout: Lock,
+        signature: Signature,
+    },
+    ClaimHtlc {
+        htlc: Hash,
+        preimage: Preimage,
+        time: UtcDateTime,
         receiver: PublicKey,
         signature: Signature,
     },
+    RefundHtlc {
+        htlc: Hash,
+        time: UtcDateTime,
+        sender: PublicKey,
+        signature: Signature,
+    },
+    // Newly issued coins paid out by the miner of the block, which signs it along with the block.
+    Reward {
+        amount: Amount,
+        receiver: Address,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Output {
+    pub receiver: Address,
+    pub amount: Amount,
+}
+
+// Keeps data out of blocks before a given height or time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lock {
+    Height(u64),
+    Time(UtcDateTime),
+}
+
+// Keeps data out of blocks after a given height or time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expiry {
+    Height(u64),
+    Time(UtcDateTime),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,9 +155,22 @@ pub struct Block {
     pub hash: Hash,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub id: u64,
+    pub nonce: u64,
+    pub miner: PublicKey,
+    pub time: UtcDateTime,
+    pub previous_hash: Hash,
+    pub signature: Signature,
+    pub hash: Hash,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockChain {
+    params: ChainParams,
     chain: Vec<Block>,
+    side: Vec<Block>,
 }
 
 impl BlockData {
@@ -66,6 +195,85 @@ impl BlockData {
                 time,
                 sender,
                 receiver,
+                memo,
+                lock,
+                expiry,
+                ..
+            } => {
+                let serialized = serde_json::json!({
+                    "fee": fee,
+                    "amount": amount,
+                    "time": time,
+                    "sender": sender,
+                    "receiver": receiver,
+                    "memo": memo,
+                    "lock": lock,
+                    "expiry": expiry,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+            Self::BatchTransaction {
+                fee,
+                time,
+                sender,
+                outputs,
+                memo,
+                expiry,
+                ..
+            } => {
+                let serialized = serde_json::json!({
+                    "fee": fee,
+                    "time": time,
+                    "sender": sender,
+                    "outputs": outputs,
+                    "memo": memo,
+                    "expiry": expiry,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+            Self::CreateMultisigWallet {
+                id, owners, time, ..
+            } => {
+                let serialized = serde_json::json!({
+                    "id": id,
+                    "owners": owners,
+                    "time": time,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+            Self::MultisigTransaction {
+                fee,
+                amount,
+                time,
+                sender,
+                receiver,
+                memo,
+                expiry,
+                ..
+            } => {
+                let serialized = serde_json::json!({
+                    "fee": fee,
+                    "amount": amount,
+                    "time": time,
+                    "sender": sender,
+                    "receiver": receiver,
+                    "memo": memo,
+                    "expiry": expiry,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+            Self::CreateHtlc {
+                fee,
+                amount,
+                time,
+                sender,
+                receiver,
+                hash_lock,
+                timeout,
                 ..
             } => {
                 let serialized = serde_json::json!({
@@ -74,13 +282,220 @@ impl BlockData {
                     "time": time,
                     "sender": sender,
                     "receiver": receiver,
+                    "hash_lock": hash_lock,
+                    "timeout": timeout,
                 });
 
                 hasher.update(serialized.to_string().as_bytes());
             }
+            Self::ClaimHtlc {
+                htlc,
+                preimage,
+                time,
+                receiver,
+                ..
+            } => {
+                let serialized = serde_json::json!({
+                    "claim": htlc,
+                    "preimage": preimage,
+                    "time": time,
+                    "receiver": receiver,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+            Self::RefundHtlc {
+                htlc, time, sender, ..
+            } => {
+                let serialized = serde_json::json!({
+                    "refund": htlc,
+                    "time": time,
+                    "sender": sender,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+            Self::Reward { amount, receiver } => {
+                let serialized = serde_json::json!({
+                    "reward": amount,
+                    "receiver": receiver,
+                });
+
+                hasher.update(serialized.to_string().as_bytes());
+            }
+        }
+
+        Hash::new(hasher.finalize().into())
+    }
+
+    pub fn memo(&self) -> Option<&Memo> {
+        match self {
+            Self::Transaction { memo, .. }
+            | Self::BatchTransaction { memo, .. }
+            | Self::MultisigTransaction { memo, .. } => memo.as_ref(),
+            _ => None,
+        }
+    }
+
+    // Whether the data may go into a block with the given ID and time.
+    pub fn is_unlocked(&self, id: u64, time: &UtcDateTime) -> bool {
+        match self {
+            Self::Transaction {
+                lock: Some(lock), ..
+            } => lock.is_unlocked(id, time),
+            _ => true,
+        }
+    }
+
+    pub fn expiry(&self) -> Option<&Expiry> {
+        match self {
+            Self::Transaction { expiry, .. }
+            | Self::BatchTransaction { expiry, .. }
+            | Self::MultisigTransaction { expiry, .. } => expiry.as_ref(),
+            _ => None,
+        }
+    }
+
+    // Whether the data is too old to go into a block with the given ID and time.
+    pub fn is_expired(&self, id: u64, time: &UtcDateTime) -> bool {
+        self.expiry()
+            .is_some_and(|expiry| expiry.is_expired(id, time))
+    }
+
+    pub fn sign(&mut self, signer: &dyn Signer) -> bool {
+        let Some(signature) = signer.sign(&self.hash()) else {
+            return false;
+        };
+
+        self.add_signature(&signer.public_key(), signature)
+    }
+
+    pub fn sign_with_rng(
+        &mut self,
+        rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        key: &PrivateKey,
+    ) -> bool {
+        let Some(signature) = key.sign_with_rng(rng, &self.hash()) else {
+            return false;
+        };
+
+        self.add_signature(&PublicKey::from(key), signature)
+    }
+
+    fn add_signature(&mut self, key: &PublicKey, new_signature: Signature) -> bool {
+        match self {
+            Self::CreateWallet {
+                key: signer,
+                signature,
+                ..
+            }
+            | Self::Transaction {
+                sender: signer,
+                signature,
+                ..
+            }
+            | Self::BatchTransaction {
+                sender: signer,
+                signature,
+                ..
+            }
+            | Self::CreateHtlc {
+                sender: signer,
+                signature,
+                ..
+            }
+            | Self::ClaimHtlc {
+                receiver: signer,
+                signature,
+                ..
+            }
+            | Self::RefundHtlc {
+                sender: signer,
+                signature,
+                ..
+            } => {
+                if signer != key {
+                    return false;
+                }
+
+                *signature = new_signature;
+            }
+            Self::CreateMultisigWallet {
+                owners: multisig,
+                signatures,
+                ..
+            }
+            | Self::MultisigTransaction {
+                sender: multisig,
+                signatures,
+                ..
+            } => {
+                let Some(position) = multisig.position(key) else {
+                    return false;
+                };
+
+                signatures.resize(multisig.keys().len(), None);
+                signatures[position] = Some(new_signature);
+            }
+            Self::Reward { .. } => return false,
+        }
+
+        true
+    }
+
+    pub fn verify(&self) -> bool {
+        let hash = self.hash();
+
+        match self {
+            Self::CreateWallet { key, signature, .. } => key.verify(&hash, signature),
+            Self::Transaction {
+                sender, signature, ..
+            }
+            | Self::BatchTransaction {
+                sender, signature, ..
+            }
+            | Self::CreateHtlc {
+                sender, signature, ..
+            }
+            | Self::ClaimHtlc {
+                receiver: sender,
+                signature,
+                ..
+            }
+            | Self::RefundHtlc {
+                sender, signature, ..
+            } => sender.verify(&hash, signature),
+            Self::CreateMultisigWallet {
+                owners: multisig,
+                signatures,
+                ..
+            }
+            | Self::MultisigTransaction {
+                sender: multisig,
+                signatures,
+                ..
+            } => multisig.verify(&hash, signatures),
+            // Only valid as part of a block, under the miner's signature.
+            Self::Reward { .. } => false,
         }
+    }
+}
 
-        Hash::new(hasher.finalize().to_vec())
+impl Lock {
+    pub fn is_unlocked(&self, id: u64, time: &UtcDateTime) -> bool {
+        match self {
+            Self::Height(height) => id >= *height,
+            Self::Time(unlock) => time >= unlock,
+        }
+    }
+}
+
+impl Expiry {
+    pub fn is_expired(&self, id: u64, time: &UtcDateTime) -> bool {
+        match self {
+            Self::Height(height) => id > *height,
+            Self::Time(expiry) => time > expiry,
+        }
     }
 }
 
@@ -101,7 +516,7 @@ impl Block {
 
         hasher.update(serialized.to_string().as_bytes());
 
-        Hash::new(hasher.finalize().to_vec())
+        Hash::new(hasher.finalize().into())
     }
 
     pub fn signed_hash(&self) -> Hash {
@@ -121,87 +536,506 @@ impl Block {
 
         hasher.update(serialized.to_string().as_bytes());
 
-        Hash::new(hasher.finalize().to_vec())
+        Hash::new(hasher.finalize().into())
+    }
+
+    // Signs the block with increasing nonces until its hash meets the difficulty.
+    pub fn mine(&mut self, params: &ChainParams, signer: &dyn Signer) -> bool {
+        self.hash = loop {
+            let Some(signature) = signer.sign(&self.hash()) else {
+                return false;
+            };
+
+            self.signature = signature;
+
+            let hash = self.signed_hash();
+
+            if params.meets_difficulty(&hash) {
+                break hash;
+            }
+
+            self.nonce += 1;
+        };
+
+        true
+    }
+
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            id: self.id,
+            nonce: self.nonce,
+            miner: self.miner.clone(),
+            time: self.time.clone(),
+            previous_hash: self.previous_hash,
+            signature: self.signature.clone(),
+            hash: self.hash,
+        }
+    }
+
+    pub fn verify(&self, params: &ChainParams) -> bool {
+        self.verify_hash(params)
+            && self.data.len() <= params.max_block_data
+            && self.verify_reward(params)
+            && self.miner.verify(&self.hash(), &self.signature)
+    }
+
+    pub fn verify_hash(&self, params: &ChainParams) -> bool {
+        self.hash == self.signed_hash() && params.meets_difficulty(&self.hash)
+    }
+
+    // The coins this block brings into circulation through its reward and new wallets.
+    pub fn issued(&self, params: &ChainParams) -> Option<Amount> {
+        Amount::checked_sum(self.data.iter().map(|data| match data {
+            BlockData::Reward { amount, .. } => *amount,
+            BlockData::CreateWallet { .. } => params.wallet_grant,
+            _ => Amount::ZERO,
+        }))
+    }
+
+    pub fn reward(&self) -> Option<(Amount, &Address)> {
+        match self.data.first() {
+            Some(BlockData::Reward { amount, receiver }) => Some((*amount, receiver)),
+            _ => None,
+        }
+    }
+
+    // A block may pay itself a reward of up to the amount the policy allows at its height, as its
+    // first entry.
+    pub fn verify_reward(&self, params: &ChainParams) -> bool {
+        let rewards = self
+            .data
+            .iter()
+            .filter(|data| matches!(data, BlockData::Reward { .. }))
+            .count();
+
+        match self.reward() {
+            Some((amount, _)) => rewards == 1 && amount <= params.reward(self.id),
+            None => rewards == 0,
+        }
+    }
+}
+
+impl BlockHeader {
+    pub fn is_genesis(&self) -> bool {
+        self.id == 0 && self.previous_hash == Hash::zero()
+    }
+
+    pub fn follows(&self, previous: &BlockHeader, params: &ChainParams) -> bool {
+        self.id == previous.id + 1
+            && self.previous_hash == previous.hash
+            && params.meets_difficulty(&self.hash)
     }
 }
 
 impl BlockChain {
-    pub const HASH_DIFFICULTY: &[u8] = &[0xFC];
+    pub fn new(params: ChainParams) -> Self {
+        Self {
+            params,
+            chain: Vec::new(),
+            side: Vec::new(),
+        }
+    }
+
+    pub fn params(&self) -> &ChainParams {
+        &self.params
+    }
 
-    pub fn new() -> Self {
-        Self { chain: Vec::new() }
+    // The coins in circulation after the block at the given height: everything paid out through
+    // block rewards and wallet grants, as no coins are ever destroyed.
+    pub fn supply(&self, height: u64) -> Option<Amount> {
+        let blocks = self.chain.get(..=usize::try_from(height).ok()?)?;
+
+        blocks.iter().try_fold(Amount::ZERO, |supply, block| {
+            supply.checked_add(block.issued(&self.params)?)
+        })
     }
 
     pub fn blocks(&self) -> &[Block] {
         &self.chain
     }
 
-    pub fn add_block(&mut self, block: Block) {
-        self.chain.push(block);
+    pub fn side_blocks(&self) -> &[Block] {
+        &self.side
     }
 
-    pub fn mine_block(block: &mut Block, key: &PrivateKey) -> bool {
-        block.hash = loop {
-            let Some(signature) = key.sign(&block.hash()) else {
-                return false;
-            };
+    pub fn tip(&self) -> Option<&Block> {
+        self.chain.last()
+    }
 
-            block.signature = signature;
+    pub fn work(&self) -> u128 {
+        self.chain.len() as u128 * self.params.block_work()
+    }
 
-            let hash = block.signed_hash();
+    pub fn position(&self, hash: &Hash) -> Option<usize> {
+        self.chain.iter().position(|block| &block.hash == hash)
+    }
 
-            if hash.bytes().starts_with(Self::HASH_DIFFICULTY) {
-                break hash;
+    pub fn find(&self, hash: &Hash) -> Option<&Block> {
+        self.chain
+            .iter()
+            .chain(self.side.iter())
+            .find(|block| &block.hash == hash)
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.find(hash).is_some()
+    }
+
+    pub fn locator(&self) -> Vec<Hash> {
+        let mut hashes = vec![];
+        let mut step = 1;
+        let mut index = self.chain.len();
+
+        while index > 0 {
+            index -= 1;
+
+            hashes.push(self.chain[index].hash);
+
+            if hashes.len() >= 10 {
+                step *= 2;
             }
 
-            block.nonce += 1;
+            index = index.saturating_sub(step - 1);
+        }
+
+        if let Some(genesis) = self.chain.first() {
+            if hashes.last() != Some(&genesis.hash) {
+                hashes.push(genesis.hash);
+            }
+        }
+
+        hashes
+    }
+
+    pub fn verify_side(&self, block: &Block) -> bool {
+        if !block.verify(&self.params) || self.contains(&block.hash) {
+            return false;
+        }
+
+        let Some(parent) = self.find(&block.previous_hash) else {
+            return false;
         };
 
-        true
+        parent.id >= self.final_height() && block.header().follows(&parent.header(), &self.params)
     }
 
-    pub fn mine_block_with_rng(
-        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
-        block: &mut Block,
-        key: &PrivateKey,
-    ) -> bool {
-        block.hash = loop {
-            let Some(signature) = key.sign_with_rng(&mut rng, &block.hash()) else {
-                return false;
-            };
+    // Blocks at or below this height can no longer be replaced by another branch.
+    pub fn final_height(&self) -> u64 {
+        self.tip()
+            .map_or(0, |tip| tip.id.saturating_sub(self.params.max_reorg_depth))
+    }
 
-            block.signature = signature;
+    pub fn add_side(&mut self, block: Block) {
+        self.side.push(block);
+    }
 
-            let hash = block.signed_hash();
+    pub fn take_side(&mut self, hash: &Hash) -> Option<Block> {
+        let position = self.side.iter().position(|block| &block.hash == hash)?;
 
-            if hash.bytes().starts_with(Self::HASH_DIFFICULTY) {
-                break hash;
+        Some(self.side.remove(position))
+    }
+
+    pub fn discard_side(&mut self, hash: &Hash) {
+        let mut hashes = vec![*hash];
+
+        while let Some(hash) = hashes.pop() {
+            self.side.retain(|block| {
+                if block.previous_hash == hash {
+                    hashes.push(block.hash);
+                }
+
+                block.hash != hash && block.previous_hash != hash
+            });
+        }
+    }
+
+    pub fn branch(&self, hash: &Hash) -> Option<(usize, Vec<Block>)> {
+        let mut branch = vec![];
+        let mut hash = *hash;
+
+        let fork = loop {
+            if let Some(position) = self.position(&hash) {
+                break position;
             }
 
-            block.nonce += 1;
+            let block = self.side.iter().find(|block| block.hash == hash)?;
+
+            hash = block.previous_hash;
+
+            branch.push(block.clone());
         };
 
-        true
+        branch.reverse();
+
+        Some((fork, branch))
+    }
+
+    pub fn branch_work(&self, hash: &Hash) -> Option<u128> {
+        let (fork, branch) = self.branch(hash)?;
+
+        Some((fork + 1 + branch.len()) as u128 * self.params.block_work())
+    }
+
+    pub fn disconnect_block(&mut self) -> Option<Block> {
+        let block = self.chain.pop()?;
+
+        self.side.push(block.clone());
+
+        Some(block)
+    }
+
+    pub fn verify_next(&self, block: &Block) -> bool {
+        if !block.verify(&self.params) {
+            return false;
+        }
+
+        match self.chain.last() {
+            Some(last) => block.header().follows(&last.header(), &self.params),
+            None => block.header().is_genesis(),
+        }
+    }
+
+    // Side blocks that could only ever be part of a branch forking off below the final height are
+    // dropped.
+    pub fn add_block(&mut self, block: Block) {
+        self.chain.push(block);
+
+        let final_height = self.final_height();
+
+        self.side.retain(|block| block.id > final_height);
+    }
+
+    pub fn mine_block(&self, block: &mut Block, signer: &dyn Signer) -> bool {
+        block.mine(&self.params, signer)
     }
 
     pub fn validate(&self) -> bool {
+        self.verify_links() && self.verify_signatures().is_ok()
+    }
+
+    pub fn verify_links(&self) -> bool {
         for i in 1..self.chain.len() {
             let a = &self.chain[i - 1];
             let b = &self.chain[i];
 
-            if b.id != a.id + 1
-                || a.hash != a.signed_hash()
-                || !a.hash.bytes().starts_with(Self::HASH_DIFFICULTY)
-                || b.hash != b.signed_hash()
-                || !b.hash.bytes().starts_with(Self::HASH_DIFFICULTY)
-                || b.previous_hash != a.hash
-                || !a.miner.verify(&a.hash(), &a.signature)
-                || !b.miner.verify(&b.hash(), &b.signature)
+            if !a.verify_hash(&self.params)
+                || !b.verify_hash(&self.params)
+                || !b.header().follows(&a.header(), &self.params)
             {
                 return false;
             }
         }
 
-        true
+        self.chain.iter().all(|block| {
+            block.data.len() <= self.params.max_block_data && block.verify_reward(&self.params)
+        })
+    }
+
+    pub fn verify_signatures(&self) -> Result<(), SignedItem> {
+        let mut batch = SignatureBatch::new();
+
+        for block in &self.chain {
+            batch.add_block(block);
+        }
+
+        batch.verify()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(length: u64) -> BlockChain {
+        let key = PrivateKey::random(rand_core::OsRng);
+        let mut chain = BlockChain::new(ChainParams::regtest());
+        let mut previous_hash = Hash::zero();
+
+        for id in 0..length {
+            let mut block = Block {
+                id,
+                nonce: 0,
+                miner: PublicKey::from(&key),
+                time: UtcDateTime::now().unwrap(),
+                data: vec![],
+                previous_hash,
+                signature: Signature::placeholder(),
+                hash: Hash::zero(),
+            };
+
+            assert!(chain.mine_block(&mut block, &key));
+            assert!(chain.verify_next(&block));
+
+            previous_hash = block.hash;
+
+            chain.add_block(block);
+        }
+
+        chain
+    }
+
+    #[test]
+    fn locator_is_dense_near_the_tip_and_ends_at_genesis() {
+        let chain = chain(100);
+
+        let ids: Vec<_> = chain
+            .locator()
+            .iter()
+            .map(|hash| chain.find(hash).unwrap().id)
+            .collect();
+
+        assert_eq!(
+            ids,
+            vec![99, 98, 97, 96, 95, 94, 93, 92, 91, 90, 88, 84, 76, 60, 28, 0]
+        );
+    }
+
+    #[test]
+    fn short_locators_list_every_block() {
+        assert!(chain(0).locator().is_empty());
+
+        let chain = chain(3);
+        let hashes: Vec<_> = chain
+            .blocks()
+            .iter()
+            .rev()
+            .map(|block| block.hash)
+            .collect();
+
+        assert_eq!(chain.locator(), hashes);
+    }
+
+    #[test]
+    fn only_the_signer_can_sign_data() {
+        let key = PrivateKey::random(rand_core::OsRng);
+        let other = PrivateKey::random(rand_core::OsRng);
+
+        let mut data = BlockData::CreateWallet {
+            id: 0,
+            key: PublicKey::from(&key),
+            time: UtcDateTime::now().unwrap(),
+            signature: Signature::placeholder(),
+        };
+
+        assert!(!data.sign(&other));
+        assert!(!data.verify());
+
+        assert!(data.sign(&key));
+        assert!(data.verify());
+    }
+
+    fn memo_transaction(key: &PrivateKey, memo: Option<Memo>) -> BlockData {
+        BlockData::Transaction {
+            fee: Amount::ZERO,
+            amount: Amount::from_units(1),
+            time: UtcDateTime::now().unwrap(),
+            sender: PublicKey::from(key),
+            receiver: Address::new(Network::Regtest, &PublicKey::from(key)),
+            memo,
+            lock: None,
+            expiry: None,
+            signature: Signature::placeholder(),
+        }
+    }
+
+    #[test]
+    fn memos_are_covered_by_the_signature() {
+        let key = PrivateKey::random(rand_core::OsRng);
+        let mut data = memo_transaction(&key, Some(Memo::Text("rent".into())));
+
+        assert!(data.sign(&key));
+        assert!(data.verify());
+
+        let memos = [
+            Some(Memo::Text("rent!".into())),
+            Some(Memo::encrypt(
+                rand_core::OsRng,
+                &PublicKey::from(&key),
+                "rent",
+            )),
+            None,
+        ];
+
+        for memo in memos {
+            let mut changed = data.clone();
+
+            if let BlockData::Transaction { memo: old, .. } = &mut changed {
+                *old = memo;
+            }
+
+            assert!(!changed.verify());
+        }
+    }
+
+    #[test]
+    fn memos_are_limited_in_length_and_characters() {
+        let params = ChainParams::regtest();
+        let key = PublicKey::from(&PrivateKey::random(rand_core::OsRng));
+        let text = |text: &str| Memo::Text(text.into());
+        let longest = "a".repeat(params.max_memo_len);
+        let too_long = "a".repeat(params.max_memo_len + 1);
+
+        assert!(params.check_memo(None));
+        assert!(params.check_memo(Some(&text("lunch money"))));
+        assert!(params.check_memo(Some(&text(&longest))));
+        assert!(!params.check_memo(Some(&text(&too_long))));
+
+        for invalid in [
+            "two\nlines",
+            "carriage\rreturn",
+            "tab\there",
+            "bell\u{7}",
+            "del\u{7f}",
+        ] {
+            assert!(!params.check_memo(Some(&text(invalid))), "{:?}", invalid);
+        }
+
+        let encrypted = |text: &str| Memo::encrypt(rand_core::OsRng, &key, text);
+
+        assert!(params.check_memo(Some(&encrypted(&longest))));
+        assert!(!params.check_memo(Some(&encrypted(&too_long))));
+    }
+
+    fn rewarded_block(id: u64, data: Vec<BlockData>) -> Block {
+        Block {
+            id,
+            nonce: 0,
+            miner: PublicKey::from(&PrivateKey::random(rand_core::OsRng)),
+            time: UtcDateTime::now().unwrap(),
+            data,
+            previous_hash: Hash::zero(),
+            signature: Signature::placeholder(),
+            hash: Hash::zero(),
+        }
+    }
+
+    #[test]
+    fn rewards_are_limited_to_one_first_entry_of_the_allowed_amount() {
+        let params = ChainParams::regtest();
+        let key = PrivateKey::random(rand_core::OsRng);
+        let reward = |amount: Amount| BlockData::Reward {
+            amount,
+            receiver: Address::new(Network::Regtest, &PublicKey::from(&key)),
+        };
+        let allowed = params.reward(1);
+        let too_much = allowed.checked_add(Amount::from_units(1)).unwrap();
+        let transaction = memo_transaction(&key, None);
+
+        assert!(rewarded_block(1, vec![]).verify_reward(&params));
+        assert!(rewarded_block(1, vec![reward(allowed)]).verify_reward(&params));
+        assert!(rewarded_block(1, vec![reward(Amount::ZERO)]).verify_reward(&params));
+        assert!(!rewarded_block(1, vec![reward(too_much)]).verify_reward(&params));
+
+        let twice = vec![reward(Amount::ZERO), reward(Amount::ZERO)];
+
+        assert!(!rewarded_block(1, twice).verify_reward(&params));
+
+        let last = vec![transaction.clone(), reward(allowed)];
+
+        assert!(!rewarded_block(1, last).verify_reward(&params));
+        assert!(rewarded_block(1, vec![reward(allowed), transaction]).verify_reward(&params));
+
+        // The genesis block pays no reward.
+        assert!(!rewarded_block(0, vec![reward(allowed)]).verify_reward(&params));
     }
 }
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::{PrivateKey, PublicKey};
+
+// A note attached to a transaction, either readable by everyone or encrypted to the receiver.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Memo {
+    Text(String),
+    Encrypted(EncryptedMemo),
+}
+
+// ECIES: the sender agrees on a key with the receiver's public key through ECDH with a one-time
+// key, and encrypts the memo with it using XChaCha20-Poly1305.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedMemo {
+    pub ephemeral_key: PublicKey,
+    #[serde(with = "hex::serde")]
+    pub nonce: [u8; 24],
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoError {
+    Decryption,
+    InvalidText,
+}
+
+impl Memo {
+    pub fn encrypt(
+        rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        receiver: &PublicKey,
+        text: &str,
+    ) -> Self {
+        Self::Encrypted(EncryptedMemo::encrypt(rng, receiver, text))
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::Encrypted(_) => None,
+        }
+    }
+}
+
+impl EncryptedMemo {
+    // The length of the Poly1305 tag added to every ciphertext.
+    pub const TAG_LEN: usize = 16;
+
+    pub fn encrypt(
+        mut rng: impl rand_core::CryptoRng + rand_core::RngCore,
+        receiver: &PublicKey,
+        text: &str,
+    ) -> Self {
+        let ephemeral = PrivateKey::random(&mut rng);
+        let ephemeral_key = PublicKey::from(&ephemeral);
+
+        let mut nonce = [0; 24];
+
+        rng.fill_bytes(&mut nonce);
+
+        let cipher = cipher(&ephemeral.shared_secret(receiver), &ephemeral_key);
+
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce), text.as_bytes())
+            .expect("memos are short enough to encrypt");
+
+        Self {
+            ephemeral_key,
+            nonce,
+            ciphertext,
+        }
+    }
+
+    pub fn decrypt(&self, key: &PrivateKey) -> Result<String, MemoError> {
+        let cipher = cipher(&key.shared_secret(&self.ephemeral_key), &self.ephemeral_key);
+
+        let Ok(bytes) = cipher.decrypt(XNonce::from_slice(&self.nonce), self.ciphertext.as_ref())
+        else {
+            return Err(MemoError::Decryption);
+        };
+
+        String::from_utf8(bytes).map_err(|_| MemoError::InvalidText)
+    }
+
+    // The length of the memo before it was encrypted.
+    pub fn len(&self) -> usize {
+        self.ciphertext.len().saturating_sub(Self::TAG_LEN)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn cipher(shared_secret: &[u8; 32], ephemeral_key: &PublicKey) -> XChaCha20Poly1305 {
+    let key: Zeroizing<[u8; 32]> = Zeroizing::new(
+        Sha256::new()
+            .chain_update(b"farcoin memo")
+            .chain_update(shared_secret)
+            .chain_update(ephemeral_key.to_bytes())
+            .finalize()
+            .into(),
+    );
+
+    XChaCha20Poly1305::new(key.as_ref().into())
+}
+
+impl fmt::Display for MemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decryption => f.write_str("not encrypted to this key or corrupted"),
+            Self::InvalidText => f.write_str("decrypted memo is not valid text"),
+        }
+    }
+}
+
+impl std::error::Error for MemoError {}
+
+#[cfg(test)]
+mod tests {
+    use rand_core::OsRng;
+
+    use super::*;
+    use crate::Scheme;
+
+    fn encrypted(receiver: &PrivateKey, text: &str) -> EncryptedMemo {
+        EncryptedMemo::encrypt(OsRng, &PublicKey::from(receiver), text)
+    }
+
+    #[test]
+    fn memos_decrypt_with_the_receiver_key() {
+        let receiver = PrivateKey::random(OsRng);
+        let memo = encrypted(&receiver, "lunch money");
+
+        assert_eq!(memo.len(), "lunch money".len());
+        assert_eq!(memo.decrypt(&receiver).unwrap(), "lunch money");
+
+        let Memo::Encrypted(memo) = Memo::encrypt(OsRng, &PublicKey::from(&receiver), "") else {
+            panic!("memo is encrypted");
+        };
+
+        assert!(memo.is_empty());
+        assert_eq!(memo.decrypt(&receiver).unwrap(), "");
+    }
+
+    #[test]
+    fn memos_decrypt_for_schnorr_receivers() {
+        let receiver = PrivateKey::random(OsRng).with_scheme(Scheme::Schnorr);
+        let memo = encrypted(&receiver, "for a schnorr key");
+
+        assert_eq!(memo.ephemeral_key.scheme(), Scheme::Ecdsa);
+        assert_eq!(memo.decrypt(&receiver).unwrap(), "for a schnorr key");
+    }
+
+    // The sender only holds its own key, not the one-time key the memo was encrypted with.
+    #[test]
+    fn other_keys_cannot_decrypt_memos() {
+        let sender = PrivateKey::random(OsRng);
+        let receiver = PrivateKey::random(OsRng);
+        let memo = encrypted(&receiver, "secret");
+
+        assert_eq!(memo.decrypt(&sender), Err(MemoError::Decryption));
+        assert_eq!(
+            memo.decrypt(&PrivateKey::random(OsRng)),
+            Err(MemoError::Decryption)
+        );
+    }
+
+    #[test]
+    fn tampered_memos_fail_to_decrypt() {
+        let receiver = PrivateKey::random(OsRng);
+        let memo = encrypted(&receiver, "secret");
+
+        let mut ciphertext = memo.clone();
+        ciphertext.ciphertext[0] ^= 1;
+
+        let mut nonce = memo.clone();
+        nonce.nonce[0] ^= 1;
+
+        let mut ephemeral_key = memo;
+        ephemeral_key.ephemeral_key = PublicKey::from(&PrivateKey::random(OsRng));
+
+        for memo in [ciphertext, nonce, ephemeral_key] {
+            assert_eq!(memo.decrypt(&receiver), Err(MemoError::Decryption));
+        }
+    }
+}
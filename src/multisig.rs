@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Hash, PublicKey, Signature};
+
+// An m-of-n policy: spending needs valid signatures from at least `threshold` of `keys`.
+// Signatures travel as a list with one slot per key, in the same order as the keys.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Multisig {
+    threshold: u32,
+    keys: Vec<PublicKey>,
+}
+
+impl Multisig {
+    pub const MAX_KEYS: usize = 16;
+
+    pub fn new(threshold: u32, keys: Vec<PublicKey>) -> Option<Self> {
+        let multisig = Self { threshold, keys };
+
+        multisig.is_valid().then_some(multisig)
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+
+    pub fn position(&self, key: &PublicKey) -> Option<usize> {
+        self.keys.iter().position(|k| k == key)
+    }
+
+    // Deserialized policies skip `new`, so the ledger checks them again.
+    pub fn is_valid(&self) -> bool {
+        if self.threshold == 0 || self.threshold as usize > self.keys.len() {
+            return false;
+        }
+
+        if self.keys.len() > Self::MAX_KEYS {
+            return false;
+        }
+
+        self.keys
+            .iter()
+            .enumerate()
+            .all(|(i, key)| !self.keys[..i].contains(key))
+    }
+
+    pub fn is_satisfied(&self, signatures: &[Option<Signature>]) -> bool {
+        signatures.len() == self.keys.len()
+            && signatures.iter().flatten().count() >= self.threshold as usize
+    }
+
+    pub fn signatures<'a>(
+        &'a self,
+        signatures: &'a [Option<Signature>],
+    ) -> impl Iterator<Item = (&'a PublicKey, &'a Signature)> {
+        self.keys
+            .iter()
+            .zip(signatures)
+            .filter_map(|(key, signature)| Some((key, signature.as_ref()?)))
+    }
+
+    pub fn verify(&self, hash: &Hash, signatures: &[Option<Signature>]) -> bool {
+        self.is_valid()
+            && self.is_satisfied(signatures)
+            && self
+                .signatures(signatures)
+                .all(|(key, signature)| key.verify(hash, signature))
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.threshold.to_be_bytes().to_vec();
+
+        for key in &self.keys {
+            let key = key.to_bytes();
+
+            bytes.push(key.len() as u8);
+            bytes.extend(key);
+        }
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PrivateKey;
+
+    fn owners(n: usize) -> (Vec<PrivateKey>, Vec<PublicKey>) {
+        let keys: Vec<_> = (0..n)
+            .map(|_| PrivateKey::random(rand_core::OsRng))
+            .collect();
+        let public_keys = keys.iter().map(PublicKey::from).collect();
+
+        (keys, public_keys)
+    }
+
+    #[test]
+    fn thresholds_must_be_reachable() {
+        let (_, keys) = owners(3);
+
+        assert!(Multisig::new(0, keys.clone()).is_none());
+        assert!(Multisig::new(4, keys.clone()).is_none());
+        assert!(Multisig::new(1, keys.clone()).is_some());
+        assert!(Multisig::new(3, keys).is_some());
+
+        let (_, keys) = owners(Multisig::MAX_KEYS + 1);
+
+        assert!(Multisig::new(1, keys).is_none());
+    }
+
+    #[test]
+    fn owners_only_count_once() {
+        let (keys, public_keys) = owners(2);
+        let duplicated = vec![
+            public_keys[0].clone(),
+            public_keys[0].clone(),
+            public_keys[1].clone(),
+        ];
+
+        assert!(Multisig::new(2, duplicated.clone()).is_none());
+
+        // A policy that skipped `new` is still refused.
+        let json = serde_json::json!({ "threshold": 2, "keys": duplicated });
+        let multisig: Multisig = serde_json::from_value(json).unwrap();
+        let hash = Hash::new([7; 32]);
+        let signature = keys[0].sign(&hash);
+
+        assert!(!multisig.is_valid());
+        assert!(!multisig.verify(&hash, &[signature.clone(), signature, None]));
+    }
+
+    #[test]
+    fn spending_needs_the_threshold_of_distinct_signatures() {
+        let (keys, public_keys) = owners(3);
+        let multisig = Multisig::new(2, public_keys).unwrap();
+        let hash = Hash::new([7; 32]);
+        let signatures: Vec<_> = keys.iter().map(|key| key.sign(&hash)).collect();
+
+        assert!(!multisig.verify(&hash, &[signatures[0].clone(), None, None]));
+        assert!(!multisig.verify(&hash, &[signatures[0].clone(), signatures[1].clone()]));
+
+        // One owner's signature in another owner's slot does not count.
+        let repeated = [signatures[0].clone(), signatures[0].clone(), None];
+
+        assert!(multisig.is_satisfied(&repeated));
+        assert!(!multisig.verify(&hash, &repeated));
+
+        assert!(multisig.verify(&hash, &[signatures[0].clone(), None, signatures[2].clone()]));
+        assert!(multisig.verify(&hash, &signatures));
+    }
+}
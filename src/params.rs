@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Amount, Hash, Memo, Network};
+
+// The consensus and economic rules of a chain. Every node of a chain must use the same ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainParams {
+    pub network: Network,
+    // Block hashes must start with these bytes.
+    #[serde(with = "hex::serde")]
+    pub hash_difficulty: Vec<u8>,
+    pub block_interval: Duration,
+    pub max_block_data: usize,
+    pub max_batch_outputs: usize,
+    // Transaction memos may be at most this many bytes long, before any encryption.
+    pub max_memo_len: usize,
+    pub block_reward: Amount,
+    // The block reward halves every this many blocks; zero keeps it constant.
+    pub halving_interval: u64,
+    // Block rewards stop once this many coins have been issued through them. Wallet grants are
+    // not counted.
+    pub max_reward_supply: Amount,
+    // Coins credited to every new student wallet.
+    pub wallet_grant: Amount,
+    pub min_fee: Amount,
+    // Blocks this far below the tip are final: no branch forking off below them is followed.
+    pub max_reorg_depth: u64,
+}
+
+impl ChainParams {
+    pub fn mainnet() -> Self {
+        Self {
+            network: Network::Mainnet,
+            hash_difficulty: vec![0xFC],
+            block_interval: Duration::from_millis(5000),
+            max_block_data: 1000,
+            max_batch_outputs: 1000,
+            max_memo_len: 128,
+            block_reward: Amount::from_units(50 * Amount::UNITS_PER_COIN),
+            halving_interval: 210_000,
+            max_reward_supply: Amount::from_units(21_000_000 * Amount::UNITS_PER_COIN),
+            wallet_grant: Amount::ZERO,
+            min_fee: Amount::from_units(Amount::UNITS_PER_COIN / 100),
+            max_reorg_depth: 100,
+        }
+    }
+
+    pub fn testnet() -> Self {
+        Self {
+            network: Network::Testnet,
+            wallet_grant: Amount::from_units(100 * Amount::UNITS_PER_COIN),
+            ..Self::mainnet()
+        }
+    }
+
+    // For local testing: blocks are quick to mine, wallets start with coins and fees are optional.
+    pub fn regtest() -> Self {
+        Self {
+            network: Network::Regtest,
+            hash_difficulty: vec![],
+            block_interval: Duration::from_millis(1000),
+            halving_interval: 150,
+            wallet_grant: Amount::from_units(100 * Amount::UNITS_PER_COIN),
+            min_fee: Amount::ZERO,
+            ..Self::mainnet()
+        }
+    }
+
+    pub fn for_network(network: Network) -> Self {
+        match network {
+            Network::Mainnet => Self::mainnet(),
+            Network::Testnet => Self::testnet(),
+            Network::Regtest => Self::regtest(),
+        }
+    }
+
+    pub fn meets_difficulty(&self, hash: &Hash) -> bool {
+        hash.bytes().starts_with(&self.hash_difficulty)
+    }
+
+    // The expected number of hashes needed to mine a block.
+    pub fn block_work(&self) -> u128 {
+        1 << (8 * self.hash_difficulty.len())
+    }
+
+    // The coins issued to the miner of the block at the given height; the genesis block pays none.
+    pub fn reward(&self, id: u64) -> Amount {
+        if id == 0 {
+            return Amount::ZERO;
+        }
+
+        self.reward_supply(id)
+            .saturating_sub(self.reward_supply(id - 1))
+    }
+
+    // The coins issued through the rewards of all blocks up to the given height.
+    pub fn reward_supply(&self, height: u64) -> Amount {
+        if self.halving_interval == 0 {
+            return self
+                .block_reward
+                .saturating_mul(height)
+                .min(self.max_reward_supply);
+        }
+
+        let mut supply = Amount::ZERO;
+        let mut reward = self.block_reward.units();
+        let mut remaining = height;
+
+        while remaining > 0 && reward > 0 {
+            let blocks = remaining.min(self.halving_interval);
+
+            supply = supply.saturating_add(Amount::from_units(reward).saturating_mul(blocks));
+            remaining -= blocks;
+            reward /= 2;
+        }
+
+        supply.min(self.max_reward_supply)
+    }
+
+    // Memos are shown as they are, so they may not contain control characters such as newlines.
+    pub fn check_memo(&self, memo: Option<&Memo>) -> bool {
+        match memo {
+            None => true,
+            Some(Memo::Text(text)) => {
+                text.len() <= self.max_memo_len && !text.chars().any(char::is_control)
+            }
+            Some(Memo::Encrypted(memo)) => memo.len() <= self.max_memo_len,
+        }
+    }
+
+    // Transactions and contracts take the fee out of the amount sent, so it has to leave the
+    // receiver something. Batch transactions pay it on top of their outputs instead, which the
+    // receivers get in full.
+    pub fn check_fee(&self, fee: Amount, amount: Amount) -> bool {
+        self.check_batch_fee(fee) && fee < amount
+    }
+
+    pub fn check_batch_fee(&self, fee: Amount) -> bool {
+        fee >= self.min_fee
+    }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use hex::{FromHex, FromHexError, ToHex};
+use serde::{Deserialize, Serialize};
+
+use crate::{BlockData, PublicKey, Signature, Signer};
+
+// Block data travelling between its signers. The signatures are kept in their own slots, one per
+// signer in the order of `signers`, and only written into the data once enough are collected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialTransaction {
+    version: u32,
+    data: BlockData,
+    signatures: Vec<Option<Signature>>,
+}
+
+#[derive(Debug)]
+pub enum PartialTransactionError {
+    Hex(FromHexError),
+    Format(serde_json::Error),
+    Invalid,
+}
+
+impl PartialTransaction {
+    pub const VERSION: u32 = 1;
+
+    // Signatures already present in the data are kept as long as they are valid.
+    pub fn new(data: BlockData) -> Self {
+        let hash = data.hash();
+
+        let signatures = match &data {
+            BlockData::CreateWallet { key, signature, .. }
+            | BlockData::Transaction {
+                sender: key,
+                signature,
+                ..
+            }
+            | BlockData::BatchTransaction {
+                sender: key,
+                signature,
+                ..
+            }
+            | BlockData::CreateHtlc {
+                sender: key,
+                signature,
+                ..
+            }
+            | BlockData::ClaimHtlc {
+                receiver: key,
+                signature,
+                ..
+            }
+            | BlockData::RefundHtlc {
+                sender: key,
+                signature,
+                ..
+            } => vec![key.verify(&hash, signature).then(|| signature.clone())],
+            BlockData::CreateMultisigWallet {
+                owners: multisig,
+                signatures,
+                ..
+            }
+            | BlockData::MultisigTransaction {
+                sender: multisig,
+                signatures,
+                ..
+            } => multisig
+                .keys()
+                .iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    signatures
+                        .get(i)
+                        .cloned()
+                        .flatten()
+                        .filter(|signature| key.verify(&hash, signature))
+                })
+                .collect(),
+            BlockData::Reward { .. } => vec![],
+        };
+
+        Self {
+            version: Self::VERSION,
+            data,
+            signatures,
+        }
+    }
+
+    pub fn data(&self) -> &BlockData {
+        &self.data
+    }
+
+    pub fn signers(&self) -> Vec<&PublicKey> {
+        match &self.data {
+            BlockData::CreateWallet { key, .. }
+            | BlockData::Transaction { sender: key, .. }
+            | BlockData::BatchTransaction { sender: key, .. }
+            | BlockData::CreateHtlc { sender: key, .. }
+            | BlockData::ClaimHtlc { receiver: key, .. }
+            | BlockData::RefundHtlc { sender: key, .. } => vec![key],
+            BlockData::CreateMultisigWallet {
+                owners: multisig, ..
+            }
+            | BlockData::MultisigTransaction {
+                sender: multisig, ..
+            } => multisig.keys().iter().collect(),
+            BlockData::Reward { .. } => vec![],
+        }
+    }
+
+    pub fn threshold(&self) -> usize {
+        match &self.data {
+            BlockData::CreateWallet { .. }
+            | BlockData::Transaction { .. }
+            | BlockData::BatchTransaction { .. }
+            | BlockData::CreateHtlc { .. }
+            | BlockData::ClaimHtlc { .. }
+            | BlockData::RefundHtlc { .. }
+            | BlockData::Reward { .. } => 1,
+            BlockData::CreateMultisigWallet {
+                owners: multisig, ..
+            }
+            | BlockData::MultisigTransaction {
+                sender: multisig, ..
+            } => multisig.threshold() as usize,
+        }
+    }
+
+    pub fn signatures(&self) -> &[Option<Signature>] {
+        &self.signatures
+    }
+
+    pub fn signed(&self) -> usize {
+        self.signatures.iter().flatten().count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.signed() >= self.threshold()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        // Rewards are signed by the miner along with their block, never on their own.
+        if let BlockData::Reward { .. } = &self.data {
+            return false;
+        }
+
+        if let BlockData::CreateMultisigWallet {
+            owners: multisig, ..
+        }
+        | BlockData::MultisigTransaction {
+            sender: multisig, ..
+        } = &self.data
+        {
+            if !multisig.is_valid() {
+                return false;
+            }
+        }
+
+        let hash = self.data.hash();
+        let signers = self.signers();
+
+        self.version == Self::VERSION
+            && self.signatures.len() == signers.len()
+            && signers
+                .iter()
+                .zip(&self.signatures)
+                .all(|(key, signature)| match signature {
+                    Some(signature) => key.verify(&hash, signature),
+                    None => true,
+                })
+    }
+
+    pub fn sign(&mut self, signer: &dyn Signer) -> bool {
+        let public_key = signer.public_key();
+
+        let Some(position) = self.signers().iter().position(|key| **key == public_key) else {
+            return false;
+        };
+
+        let Some(signature) = signer.sign(&self.data.hash()) else {
+            return false;
+        };
+
+        self.signatures[position] = Some(signature);
+
+        true
+    }
+
+    // Takes over the signatures of another copy of the same data that this copy is missing.
+    pub fn combine(&mut self, other: &PartialTransaction) -> bool {
+        if self.data.hash() != other.data.hash() || !other.is_valid() {
+            return false;
+        }
+
+        for (signature, other) in self.signatures.iter_mut().zip(&other.signatures) {
+            if signature.is_none() {
+                *signature = other.clone();
+            }
+        }
+
+        true
+    }
+
+    pub fn finalize(&self) -> Option<BlockData> {
+        if !self.is_valid() || !self.is_complete() {
+            return None;
+        }
+
+        let mut data = self.data.clone();
+
+        match &mut data {
+            BlockData::CreateWallet { signature, .. }
+            | BlockData::Transaction { signature, .. }
+            | BlockData::BatchTransaction { signature, .. }
+            | BlockData::CreateHtlc { signature, .. }
+            | BlockData::ClaimHtlc { signature, .. }
+            | BlockData::RefundHtlc { signature, .. } => {
+                *signature = self.signatures[0].clone()?;
+            }
+            BlockData::CreateMultisigWallet { signatures, .. }
+            | BlockData::MultisigTransaction { signatures, .. } => {
+                *signatures = self.signatures.clone();
+            }
+            BlockData::Reward { .. } => return None,
+        }
+
+        Some(data)
+    }
+}
+
+impl From<&PartialTransaction> for String {
+    fn from(transaction: &PartialTransaction) -> Self {
+        serde_json::to_vec(transaction)
+            .expect("partial transactions serialize")
+            .encode_hex::<String>()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PartialTransaction {
+    type Error = PartialTransactionError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let bytes = Vec::<u8>::from_hex(value.trim()).map_err(PartialTransactionError::Hex)?;
+
+        let transaction =
+            serde_json::from_slice::<Self>(&bytes).map_err(PartialTransactionError::Format)?;
+
+        if !transaction.is_valid() {
+            return Err(PartialTransactionError::Invalid);
+        }
+
+        Ok(transaction)
+    }
+}
+
+impl fmt::Display for PartialTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex(error) => write!(f, "invalid hex ({})", error),
+            Self::Format(error) => write!(f, "malformed partially signed transaction: {}", error),
+            Self::Invalid => f.write_str("unsupported version, signers or signatures"),
+        }
+    }
+}
+
+impl std::error::Error for PartialTransactionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Amount, Multisig, Network, PrivateKey, UtcDateTime};
+
+    fn owners() -> [PrivateKey; 3] {
+        [(); 3].map(|_| PrivateKey::random(rand_core::OsRng))
+    }
+
+    fn payment(owners: &[PrivateKey], amount: u64) -> PartialTransaction {
+        let multisig = Multisig::new(2, owners.iter().map(PublicKey::from).collect()).unwrap();
+        let receiver = PublicKey::from(&PrivateKey::random(rand_core::OsRng));
+
+        PartialTransaction::new(BlockData::MultisigTransaction {
+            fee: Amount::ZERO,
+            amount: Amount::from_coins(amount).unwrap(),
+            time: UtcDateTime::now().unwrap(),
+            sender: multisig,
+            receiver: Address::new(Network::Regtest, &receiver),
+            memo: None,
+            expiry: None,
+            signatures: vec![],
+        })
+    }
+
+    #[test]
+    fn partial_transactions_round_trip_through_hex() {
+        let owners = owners();
+        let mut psbt = payment(&owners, 5);
+
+        assert!(psbt.sign(&owners[1]));
+
+        let decoded = PartialTransaction::try_from(String::from(&psbt).as_str()).unwrap();
+
+        assert_eq!(decoded.data().hash(), psbt.data().hash());
+        assert_eq!(decoded.signatures(), psbt.signatures());
+        assert_eq!(decoded.signed(), 1);
+        assert!(PartialTransaction::try_from("not hex").is_err());
+    }
+
+    #[test]
+    fn copies_signed_by_different_owners_combine() {
+        let owners = owners();
+        let mut first = payment(&owners, 5);
+        let mut second = first.clone();
+
+        assert!(first.sign(&owners[0]));
+        assert!(second.sign(&owners[2]));
+        assert!(!first.is_complete());
+        assert!(first.finalize().is_none());
+
+        assert!(first.combine(&second));
+        assert_eq!(first.signed(), 2);
+        assert!(first.is_complete());
+
+        let Some(BlockData::MultisigTransaction {
+            sender, signatures, ..
+        }) = first.finalize()
+        else {
+            panic!("finalized data is a multisig transaction");
+        };
+
+        assert!(sender.verify(&first.data().hash(), &signatures));
+    }
+
+    #[test]
+    fn combining_needs_the_same_data_and_valid_signatures() {
+        let owners = owners();
+        let mut psbt = payment(&owners, 5);
+        let mut other = payment(&owners, 6);
+
+        assert!(other.sign(&owners[1]));
+        assert!(!psbt.combine(&other));
+        assert_eq!(psbt.signed(), 0);
+
+        // A signature over different data in the right slot.
+        let mut forged = psbt.clone();
+        forged.signatures[1] = other.signatures[1].clone();
+
+        assert!(!forged.is_valid());
+        assert!(!psbt.combine(&forged));
+        assert_eq!(psbt.signed(), 0);
+
+        // Someone who is not an owner cannot sign.
+        assert!(!psbt.sign(&PrivateKey::random(rand_core::OsRng)));
+    }
+}
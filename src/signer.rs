@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Hash, PrivateKey, PublicKey, Signature};
+
+pub trait Signer {
+    fn public_key(&self) -> PublicKey;
+
+    fn sign(&self, hash: &Hash) -> Option<Signature>;
+}
+
+impl Signer for PrivateKey {
+    fn public_key(&self) -> PublicKey {
+        PublicKey::from(self)
+    }
+
+    fn sign(&self, hash: &Hash) -> Option<Signature> {
+        PrivateKey::sign(self, hash)
+    }
+}
+
+// One JSON request per connection, answered by one JSON response, each on a single line.
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    PublicKey,
+    Sign { hash: Hash },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    PublicKey(PublicKey),
+    Signature(Signature),
+    Error(String),
+}
+
+#[cfg(unix)]
+pub use remote::{serve_signer, RemoteSigner};
+
+#[cfg(unix)]
+mod remote {
+    use std::{
+        io::{self, BufRead, BufReader, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        path::{Path, PathBuf},
+        thread,
+        time::Duration,
+    };
+
+    use super::{Request, Response, Signer};
+    use crate::{Hash, PublicKey, Signature};
+
+    // Signing takes milliseconds, so a side that stays silent for longer is given up on.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // A key held by another process, such as `farcoin-cli serve-signer`, reached over a Unix
+    // socket.
+    #[derive(Debug, Clone)]
+    pub struct RemoteSigner {
+        path: PathBuf,
+        public_key: PublicKey,
+    }
+
+    impl RemoteSigner {
+        pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+            let path = path.as_ref().to_path_buf();
+
+            match request(&path, &Request::PublicKey)? {
+                Response::PublicKey(public_key) => Ok(Self { path, public_key }),
+                Response::Error(message) => Err(io::Error::other(message)),
+                Response::Signature(_) => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected response from signer",
+                )),
+            }
+        }
+    }
+
+    impl Signer for RemoteSigner {
+        fn public_key(&self) -> PublicKey {
+            self.public_key.clone()
+        }
+
+        fn sign(&self, hash: &Hash) -> Option<Signature> {
+            let sign = Request::Sign { hash: *hash };
+
+            let Ok(Response::Signature(signature)) = request(&self.path, &sign) else {
+                return None;
+            };
+
+            self.public_key
+                .verify(hash, &signature)
+                .then_some(signature)
+        }
+    }
+
+    fn request(path: &Path, request: &Request) -> io::Result<Response> {
+        let mut stream = UnixStream::connect(path)?;
+
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        serde_json::to_writer(&mut stream, request)?;
+        stream.write_all(b"\n")?;
+
+        let mut line = String::new();
+
+        BufReader::new(stream).read_line(&mut line)?;
+
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    // Answers each connection on its own thread for as long as the listener accepts them. Anyone
+    // who can open the socket can have data signed, so keep it in a private directory.
+    pub fn serve_signer(listener: &UnixListener, signer: &(dyn Signer + Sync)) -> io::Result<()> {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+
+                // A misbehaving client only loses its own connection.
+                scope.spawn(move || respond(stream, signer));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn respond(stream: UnixStream, signer: &dyn Signer) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut line = String::new();
+
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::PublicKey) => Response::PublicKey(signer.public_key()),
+            Ok(Request::Sign { hash }) => match signer.sign(&hash) {
+                Some(signature) => Response::Signature(signature),
+                None => Response::Error("failed to sign".into()),
+            },
+            Err(error) => Response::Error(error.to_string()),
+        };
+
+        let mut stream = &stream;
+
+        serde_json::to_writer(stream, &response)?;
+        stream.write_all(b"\n")
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::PrivateKey;
+
+        #[test]
+        fn idle_clients_do_not_block_signing() {
+            let key = PrivateKey::random(rand_core::OsRng);
+            let public_key = PublicKey::from(&key);
+            let path = std::env::temp_dir().join(format!("farcoin-signer-{}", std::process::id()));
+
+            let _ = std::fs::remove_file(&path);
+            let listener = UnixListener::bind(&path).unwrap();
+
+            thread::spawn(move || serve_signer(&listener, &key));
+
+            // Connects without ever sending a request.
+            let _idle = UnixStream::connect(&path).unwrap();
+
+            let signer = RemoteSigner::connect(&path).unwrap();
+            let hash = Hash::zero();
+            let signature = signer.sign(&hash);
+
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(signer.public_key(), public_key);
+            assert!(public_key.verify(&hash, &signature.unwrap()));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{de, Deserialize, Serialize};
 
@@ -33,12 +33,26 @@ impl std::cmp::PartialEq for UtcDateTime {
     }
 }
 
+impl FromStr for UtcDateTime {
+    type Err = hifitime::Errors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(hifitime::Epoch::from_str(s)?))
+    }
+}
+
+impl fmt::Display for UtcDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl Serialize for UtcDateTime {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&format!("{}", self.0))
+        serializer.serialize_str(&self.to_string())
     }
 }
 
@@ -49,8 +63,6 @@ impl<'de> Deserialize<'de> for UtcDateTime {
     {
         let s = deserializer.deserialize_str(SerdeVisitor)?;
 
-        let epoch = hifitime::Epoch::from_str(&s).map_err(de::Error::custom)?;
-
-        Ok(Self(epoch))
+        Self::from_str(&s).map_err(de::Error::custom)
     }
 }